@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+use std::{fs, path::{Path, PathBuf}};
+
+use crate::{
+    config::{Config, SigningFormat},
+    paths::{expand_home, next_to_config},
+    validation::signing_key::{self, SigningKeyKind},
+};
+
+pub const FILE_NAME: &str = "allowed_signers";
+
+const HEADER: &str = "# Managed by git-profile. Run `git profile sync` to update.";
+
+/// Where the allowed signers file lives, next to the config file
+pub fn path_for(config_path: &Path) -> PathBuf {
+    next_to_config(config_path, FILE_NAME)
+}
+
+/// Reads the public key (`<type> <base64>`) of an SSH signing key.
+/// Private key paths are mapped to their `.pub` counterparts.
+fn read_public_key(signingkey: &str) -> Result<String> {
+    let content = match signing_key::kind_of(signingkey) {
+        SigningKeyKind::SshLiteral(literal) => literal.to_owned(),
+        SigningKeyKind::SshFile(path) => {
+            let path = expand_home(path);
+            let public_key_path = if path.extension().is_some_and(|extension| extension == "pub") {
+                path
+            } else {
+                PathBuf::from(format!("{}.pub", path.display()))
+            };
+            fs::read_to_string(&public_key_path)
+                .map_err(|_| anyhow!("Can't read {}", public_key_path.display()))?
+        }
+        SigningKeyKind::Gpg(_) => return Err(anyhow!("{} is not an SSH key", signingkey)),
+    };
+
+    // Drop the comment
+    let fields: Vec<&str> = content.split_whitespace().take(2).collect();
+    if fields.len() < 2 {
+        return Err(anyhow!("{} is not a valid SSH public key", signingkey));
+    }
+    Ok(fields.join(" "))
+}
+
+/// Renders the allowed signers file for every profile signing with SSH.
+/// Returns the content along with the profiles that were skipped and why.
+pub fn render(config: &Config) -> Result<(String, Vec<String>)> {
+    let mut lines: Vec<String> = vec![HEADER.to_owned()];
+    let mut warnings = vec![];
+
+    for (key, profile) in config.resolved_profiles()? {
+        let signingkey = match (profile.signingkey.as_ref(), profile.effective_signingformat()) {
+            (Some(signingkey), Some(SigningFormat::Ssh)) => signingkey,
+            _ => continue,
+        };
+        match read_public_key(signingkey) {
+            Ok(public_key) => {
+                let line = format!("{} namespaces=\"git\" {}", profile.email, public_key);
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+            Err(error) => warnings.push(format!("Skipped {}: {}", key, error)),
+        }
+    }
+
+    Ok((lines.join("\n") + "\n", warnings))
+}
+
+/// Rebuilds the allowed signers file, returning the number of signers along with warnings
+pub fn write(config: &Config, path: &Path) -> Result<(usize, Vec<String>)> {
+    let (content, warnings) = render(config)?;
+    fs::write(path, &content)?;
+    Ok((content.lines().count() - 1, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_ssh_signers() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let key_path = dir.path().join("id_ed25519");
+        fs::write(dir.path().join("id_ed25519.pub"), "ssh-ed25519 AAAAfile alice@laptop\n")?;
+
+        let config: Config = toml::from_str(&format!(
+            r#"
+            [profile.file]
+            name = 'Alice'
+            email = 'alice@example.com'
+            signingkey = '{}'
+            signingformat = 'ssh'
+
+            [profile.literal]
+            name = 'Alice'
+            email = 'alice@work.com'
+            signingkey = 'key::ssh-ed25519 AAAAliteral'
+
+            [profile.gpg]
+            name = 'Alice'
+            email = 'alice@gpg.com'
+            signingkey = 'ABCDEF'
+            signingformat = 'openpgp'
+
+            [profile.missing]
+            name = 'Alice'
+            email = 'alice@missing.com'
+            signingkey = '/nonexistent/id_rsa'
+            signingformat = 'ssh'
+        "#,
+            key_path.display()
+        ))?;
+
+        let (content, warnings) = render(&config)?;
+        assert!(content.starts_with(HEADER));
+        assert!(content.contains("\nalice@example.com namespaces=\"git\" ssh-ed25519 AAAAfile\n"));
+        assert!(content.contains("\nalice@work.com namespaces=\"git\" ssh-ed25519 AAAAliteral\n"));
+        assert!(!content.contains("alice@gpg.com"));
+        assert_eq!(warnings, vec!["Skipped missing: Can't read /nonexistent/id_rsa.pub".to_string()]);
+        Ok(())
+    }
+}
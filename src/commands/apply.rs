@@ -1,18 +1,89 @@
 use crate::{
+    allowed_signers,
+    config::SigningFormat,
     context::AppContext,
-    git::{Level}
+    git::{Level},
+    policy::Policy,
+    prompt,
 };
 use anyhow::{anyhow, Result};
 
-pub fn execute(context: &AppContext, profile_key: &str, maybe_level: &Option<Level>) -> Result<()> {
+/// Applies the profile without reporting success, for commands applying several profiles
+pub fn apply(context: &AppContext, profile_key: &str, maybe_level: &Option<Level>, force: bool) -> Result<()> {
     let config = context.config_client.load()?;
     let git_config_client = context.git_config_client.as_ref();
 
-    if let Some(profile) = config.profile.get(profile_key) {
-        git_config_client.set(&profile, &maybe_level)?;
-        println!("✨ Successfully applied {}", profile_key);
-        Ok(())
-    } else {
-        Err(anyhow!("Profile {} doesn't exist", profile_key))
+    if !config.has_profile_key(profile_key) {
+        return Err(anyhow!("Profile {} doesn't exist", profile_key));
     }
+
+    if config.is_template(profile_key) {
+        return Err(anyhow!("Profile {} is a template and can't be applied", profile_key));
+    }
+
+    let (profile, _) = config.resolve_profile(profile_key)?;
+
+    // Only the repository itself is bound by its policy
+    let is_repository_level = matches!(maybe_level, None | Some(Level::Local) | Some(Level::WorkTree));
+    if is_repository_level {
+        if let Some(policy) = Policy::current(git_config_client)? {
+            policy.enforce(profile_key, &profile, force)?;
+        }
+    }
+
+    git_config_client.set(&profile, maybe_level)?;
+
+    // Let `git log --show-signature` verify SSH signatures made with any of the profiles
+    if profile.effective_signingformat() == Some(SigningFormat::Ssh) {
+        let path = allowed_signers::path_for(context.config_client.path());
+        let (_, warnings) = allowed_signers::write(&config, &path)?;
+        warnings.iter().for_each(|warning| println!("⚠️  {}", warning));
+        git_config_client.set_value(
+            "gpg.ssh.allowedSignersFile",
+            &path.display().to_string(),
+            maybe_level,
+        )?;
+    }
+    Ok(())
+}
+
+/// Lets the user pick a profile, then the level to apply it at unless one was given
+fn pick(context: &AppContext, maybe_level: &Option<Level>) -> Result<(String, Option<Level>)> {
+    let config = context.config_client.load()?;
+    let git_config_client = context.git_config_client.as_ref();
+
+    let profile_keys: Vec<String> = config
+        .profile
+        .iter()
+        .filter(|(_, profile)| !profile.is_abstract)
+        .map(|(profile_key, _)| profile_key.to_owned())
+        .collect();
+    if profile_keys.is_empty() {
+        return Err(anyhow!("There is no profile to apply"));
+    }
+
+    let current_profile_key = git_config_client.get_profile_key(&config, &None)?;
+    let profile_key = prompt::select_profile_key(
+        &config,
+        &profile_keys,
+        "Select which profile to apply",
+        current_profile_key.as_deref(),
+    )?;
+
+    let level = match maybe_level {
+        Some(level) => level.clone(),
+        None => prompt::select_level(git_config_client)?,
+    };
+    Ok((profile_key, Some(level)))
+}
+
+pub fn execute(context: &AppContext, maybe_profile_key: Option<&str>, maybe_level: &Option<Level>, force: bool) -> Result<()> {
+    let (profile_key, maybe_level) = match maybe_profile_key {
+        Some(profile_key) => (profile_key.to_owned(), maybe_level.clone()),
+        None => pick(context, maybe_level)?,
+    };
+
+    apply(context, &profile_key, &maybe_level, force)?;
+    println!("✨ Successfully applied {}", profile_key);
+    Ok(())
 }
@@ -0,0 +1,294 @@
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::process::Command;
+
+use crate::{
+    config::Profile,
+    context::AppContext,
+    git,
+    output::OutputFormat,
+    policy::Policy,
+    rules,
+};
+
+/// `git log` fields, separated by the unit separator, commits separated by the record separator
+const LOG_FORMAT: &str = "--format=%H%x1f%an%x1f%ae%x1f%cn%x1f%ce%x1f%G?%x1f%s%x1e";
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Commit {
+    pub hash: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub committer_name: String,
+    pub committer_email: String,
+    /// %G? of `git log`: `N` when there is no signature
+    pub signature_status: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub commit: String,
+    pub subject: String,
+    pub problems: Vec<String>,
+}
+
+/// What the rule and the policy of the repository expect from its commits, both being enforced
+#[derive(Default)]
+pub struct Expectation {
+    /// Profile of the first matching rule, which the commits must be made with
+    pub rule_profile: Option<(String, Profile)>,
+    /// Profiles allowed by the policy, any if empty
+    pub profiles: Vec<(String, Profile)>,
+    pub policy: Option<Policy>,
+}
+
+impl Expectation {
+    /// The profile of the rule and those of the policy
+    fn all_profiles(&self) -> impl Iterator<Item = &(String, Profile)> {
+        self.rule_profile.iter().chain(self.profiles.iter())
+    }
+}
+
+fn parse_log(output: &str) -> Vec<Commit> {
+    output
+        .split('\x1e')
+        .map(str::trim)
+        .filter(|record| !record.is_empty())
+        .filter_map(|record| {
+            let fields: Vec<&str> = record.split('\x1f').collect();
+            match fields.as_slice() {
+                [hash, author_name, author_email, committer_name, committer_email, signature_status, subject] => {
+                    Some(Commit {
+                        hash: hash.to_string(),
+                        author_name: author_name.to_string(),
+                        author_email: author_email.to_string(),
+                        committer_name: committer_name.to_string(),
+                        committer_email: committer_email.to_string(),
+                        signature_status: signature_status.to_string(),
+                        subject: subject.to_string(),
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn check_email(role: &str, email: &str, expectation: &Expectation, problems: &mut Vec<String>) {
+    if let Some((key, profile)) = expectation.rule_profile.as_ref() {
+        if profile.email != email {
+            problems.push(format!("{} {} doesn't belong to {}", role, email, key));
+        }
+    }
+    if !expectation.profiles.is_empty() && !expectation.profiles.iter().any(|(_, profile)| profile.email == email) {
+        let keys: Vec<&str> = expectation.profiles.iter().map(|(key, _)| key.as_str()).collect();
+        problems.push(format!("{} {} doesn't belong to {}", role, email, keys.join(", ")));
+    }
+    if let Some(pattern) = expectation.policy.as_ref().and_then(|policy| policy.email.as_ref()) {
+        // Checked when the policy was loaded
+        if !Regex::new(pattern).unwrap().is_match(email) {
+            problems.push(format!("{} {} doesn't match {}", role, email, pattern));
+        }
+    }
+}
+
+pub fn audit(commits: &[Commit], expectation: &Expectation) -> Vec<Finding> {
+    let policy_requires_signing = expectation
+        .policy
+        .as_ref()
+        .is_some_and(|policy| policy.signingformat.is_some());
+
+    commits
+        .iter()
+        .filter_map(|commit| {
+            let mut problems = vec![];
+            check_email("author", &commit.author_email, expectation, &mut problems);
+            if commit.committer_email != commit.author_email {
+                check_email("committer", &commit.committer_email, expectation, &mut problems);
+            }
+
+            // Signing is required by the policy, or by a profile the commit was authored or committed with
+            let profile_requires_signing = expectation
+                .all_profiles()
+                .filter(|(_, profile)| profile.email == commit.author_email || profile.email == commit.committer_email)
+                .any(|(_, profile)| profile.signingkey.is_some());
+            if commit.signature_status == "N" && (policy_requires_signing || profile_requires_signing) {
+                problems.push("not signed".to_owned());
+            }
+
+            if problems.is_empty() {
+                None
+            } else {
+                Some(Finding {
+                    commit: commit.hash.to_owned(),
+                    subject: commit.subject.to_owned(),
+                    problems,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Builds the expectation of the current repository from the first matching rule and its policy
+fn expectation_for(context: &AppContext) -> Result<Expectation> {
+    let config = context.config_client.load()?;
+    let policy = Policy::current(context.git_config_client.as_ref())?;
+
+    let maybe_rule = git::git_dir()?.and_then(|git_dir| rules::rule_for(&config, &git_dir).cloned());
+    if maybe_rule.is_none() && policy.is_none() {
+        bail!("No rule or policy applies to this repository, so there's nothing to audit against");
+    }
+    let resolve = |key: &String| -> Result<(String, Profile)> {
+        let (profile, _) = config.resolve_profile(key)?;
+        Ok((key.to_owned(), profile))
+    };
+    let rule_profile = maybe_rule.map(|rule| resolve(&rule.profile)).transpose()?;
+    let profiles = policy
+        .iter()
+        .flat_map(|policy| policy.profiles.iter())
+        .map(resolve)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Expectation {
+        rule_profile,
+        profiles,
+        policy,
+    })
+}
+
+pub fn execute(context: &AppContext, range: &str, format: OutputFormat) -> Result<()> {
+    let expectation = expectation_for(context)?;
+
+    let output = Command::new("git").args(["log", LOG_FORMAT, range, "--"]).output()?;
+    if !output.status.success() {
+        bail!("Can't read the commits of {}: {}", range, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let commits = parse_log(&String::from_utf8(output.stdout)?);
+    let findings = audit(&commits, &expectation);
+
+    match format {
+        OutputFormat::Text if findings.is_empty() => println!("✨ All {} commit(s) look right", commits.len()),
+        OutputFormat::Text => findings.iter().for_each(|finding| {
+            let short_hash = &finding.commit[..finding.commit.len().min(12)];
+            println!("{} {}: {}", short_hash, finding.subject, finding.problems.join("; "))
+        }),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&findings)?),
+    }
+
+    if !findings.is_empty() {
+        bail!("Found {} commit(s) made with the wrong identity", findings.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(author_email: &str, committer_email: &str, signature_status: &str) -> Commit {
+        Commit {
+            hash: "0123456789abcdef".to_string(),
+            author_name: "Foo Bar".to_string(),
+            author_email: author_email.to_string(),
+            committer_name: "Foo Bar".to_string(),
+            committer_email: committer_email.to_string(),
+            signature_status: signature_status.to_string(),
+            subject: "Something".to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_log() {
+        let output = "abc\x1fFoo Bar\x1ffoo@bar.com\x1fFoo Bar\x1ffoo@bar.com\x1fG\x1fFirst\x1e\n\
+                      def\x1fFoo Bar\x1ffoo@bar.com\x1fBot\x1fbot@bar.com\x1fN\x1fSecond\x1e\n";
+        let commits = parse_log(output);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].hash, "abc");
+        assert_eq!(commits[0].signature_status, "G");
+        assert_eq!(commits[1].committer_email, "bot@bar.com");
+        assert_eq!(commits[1].subject, "Second");
+    }
+
+    #[test]
+    fn flags_wrong_identities() {
+        let work = Profile {
+            email: "foo@work.com".to_string(),
+            signingkey: Some("ABCD".to_string()),
+            ..Default::default()
+        };
+        let expectation = Expectation {
+            rule_profile: Some(("work".to_string(), work)),
+            ..Default::default()
+        };
+        let commits = vec![
+            commit("foo@work.com", "foo@work.com", "G"),
+            commit("foo@home.com", "foo@work.com", "G"),
+            commit("foo@work.com", "foo@work.com", "N"),
+        ];
+        let problems: Vec<Vec<String>> = audit(&commits, &expectation)
+            .into_iter()
+            .map(|finding| finding.problems)
+            .collect();
+        assert_eq!(
+            problems,
+            vec![vec!["author foo@home.com doesn't belong to work".to_string()], vec!["not signed".to_string()]]
+        );
+    }
+
+    #[test]
+    fn requires_the_signature_of_the_author_profile() {
+        let work = Profile {
+            email: "foo@work.com".to_string(),
+            signingkey: Some("ABCD".to_string()),
+            ..Default::default()
+        };
+        let expectation = Expectation {
+            profiles: vec![("work".to_string(), work)],
+            ..Default::default()
+        };
+        let findings = audit(&[commit("foo@work.com", "noreply@github.com", "N")], &expectation);
+        assert_eq!(
+            findings[0].problems,
+            vec!["committer noreply@github.com doesn't belong to work", "not signed"]
+        );
+    }
+
+    #[test]
+    fn enforces_both_the_rule_and_the_policy() {
+        let work = Profile {
+            email: "foo@work.com".to_string(),
+            ..Default::default()
+        };
+        let oss = Profile {
+            email: "foo@oss.org".to_string(),
+            ..Default::default()
+        };
+        let expectation = Expectation {
+            rule_profile: Some(("work".to_string(), work.clone())),
+            profiles: vec![("work".to_string(), work), ("oss".to_string(), oss)],
+            policy: None,
+        };
+        let findings = audit(
+            &[commit("foo@work.com", "foo@work.com", "N"), commit("foo@oss.org", "foo@oss.org", "N")],
+            &expectation,
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].problems, vec!["author foo@oss.org doesn't belong to work"]);
+    }
+
+    #[test]
+    fn applies_the_policy() {
+        let policy: Policy = toml::from_str("email = '@acme\\.com$'\nsigningformat = 'ssh'").unwrap();
+        let expectation = Expectation {
+            policy: Some(policy),
+            ..Default::default()
+        };
+        let findings = audit(&[commit("foo@acme.com", "bot@github.com", "N")], &expectation);
+        assert_eq!(
+            findings[0].problems,
+            vec![r"committer bot@github.com doesn't match @acme\.com$", "not signed"]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::{collections::HashSet, fmt, fs, path::Path};
+
+use crate::{
+    config::{Config, Profile},
+    context::AppContext,
+    git::Level,
+    output::OutputFormat,
+    paths::resolve_config_path,
+    validation::{email::is_email, signing_key},
+};
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    pub severity: Severity,
+    pub file: String,
+    /// 1-based line number, if the problem could be located
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}: {}", self.file, line, severity, self.message),
+            None => write!(f, "{}: {}: {}", self.file, severity, self.message),
+        }
+    }
+}
+
+fn line_of_offset(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// Finds the line of the `[profile.<key>]` header, or of `<field> = ...` inside that table
+fn locate(content: &str, profile_key: &str, maybe_field: Option<&str>) -> Option<usize> {
+    let headers = [
+        format!("profile.{}", profile_key),
+        format!("profile.\"{}\"", profile_key),
+        format!("profile.'{}'", profile_key),
+    ];
+
+    let mut lines = content.lines().enumerate().skip_while(|(_, line)| {
+        let line = line.trim();
+        let header = line.strip_prefix('[').and_then(|line| line.strip_suffix(']'));
+        !header.is_some_and(|header| headers.contains(&header.trim().to_owned()))
+    });
+    let (header_index, _) = lines.next()?;
+
+    let field = match maybe_field {
+        Some(field) => field,
+        None => return Some(header_index + 1),
+    };
+    lines
+        .take_while(|(_, line)| !line.trim_start().starts_with('['))
+        .find(|(_, line)| {
+            line.trim_start()
+                .strip_prefix(field)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+        .map(|(index, _)| index + 1)
+        // Fall back to the header if the field is not written in the file
+        .or(Some(header_index + 1))
+}
+
+fn lint_config(config: &Config, content: &str, file: &str) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut problem = |severity, line, message| {
+        problems.push(Problem {
+            severity,
+            file: file.to_owned(),
+            line,
+            message,
+        })
+    };
+
+    let mut resolved: Vec<(&String, Profile)> = vec![];
+    for (key, raw_profile) in config.profile.iter() {
+        let (profile, origins) = match config.resolve_profile(key) {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                problem(Severity::Error, locate(content, key, Some("extends")), error.to_string());
+                continue;
+            }
+        };
+        if raw_profile.is_abstract {
+            continue;
+        }
+
+        if let Some(placeholder) = profile.placeholders().first() {
+            problem(
+                Severity::Error,
+                locate(content, key, None),
+                format!("Profile {} has an unfilled placeholder {{{}}}", key, placeholder),
+            );
+            continue;
+        }
+        if profile.name.is_empty() {
+            problem(
+                Severity::Error,
+                locate(content, key, None),
+                format!("Profile {} has no name (user.name)", key),
+            );
+        }
+        if profile.email.is_empty() {
+            problem(
+                Severity::Error,
+                locate(content, key, None),
+                format!("Profile {} has no email (user.email)", key),
+            );
+        } else if is_email(&profile.email).is_err() {
+            problem(
+                Severity::Error,
+                locate(content, &origins["email"], Some("email")),
+                format!("Profile {} has an invalid email {}", key, profile.email),
+            );
+        }
+
+        // `current` and friends can't tell apart profiles with the same values
+        if let Some((duplicate_key, _)) = resolved.iter().find(|(_, other)| other.same_identity(&profile)) {
+            problem(
+                Severity::Warning,
+                locate(content, key, None),
+                format!("Profiles {} and {} have the same values", duplicate_key, key),
+            );
+        }
+        resolved.push((key, profile));
+    }
+
+    problems
+}
+
+fn lint_signing_keys(config: &Config, content: &str, file: &str) -> Vec<Problem> {
+    let mut checked: HashSet<String> = HashSet::new();
+    let mut problems = vec![];
+
+    for (key, raw_profile) in config.profile.iter() {
+        if raw_profile.is_abstract {
+            continue;
+        }
+        let Ok((profile, origins)) = config.resolve_profile(key) else {
+            continue;
+        };
+        let Some(signingkey) = profile.signingkey.as_ref() else {
+            continue;
+        };
+        // An inherited key is reported once, where it's defined
+        let origin = &origins["signingkey"];
+        if !checked.insert(origin.to_owned()) {
+            continue;
+        }
+        if let Err(error) = signing_key::exists(signingkey) {
+            problems.push(Problem {
+                severity: Severity::Error,
+                file: file.to_owned(),
+                line: locate(content, origin, Some("signingkey")),
+                message: format!("Profile {}: {}", origin, error),
+            });
+        }
+    }
+
+    problems
+}
+
+/// Looks for `includeIf` entries of the global git config pointing at missing files,
+/// or at files whose identity doesn't belong to any profile
+fn lint_includes(context: &AppContext, config: &Config) -> Result<Vec<Problem>> {
+    let git_config_client = context.git_config_client.as_ref();
+    let profiles = config.resolved_profiles().unwrap_or_default();
+    let mut problems = vec![];
+
+    let entries = git_config_client.get_regexp(r"^includeif\..*\.path$", &Some(Level::Global))?;
+    for entry in entries {
+        let file = entry.origin.strip_prefix("file:").unwrap_or(&entry.origin);
+        let condition = entry
+            .key
+            .strip_prefix("includeif.")
+            .and_then(|key| key.strip_suffix(".path"))
+            .unwrap_or(&entry.key);
+        let line = fs::read_to_string(file).ok().and_then(|content| {
+            content
+                .lines()
+                .position(|line| line.contains("path") && line.contains(&entry.value))
+                .map(|index| index + 1)
+        });
+
+        let target = resolve_config_path(&entry.value, Path::new(file));
+        if !target.is_file() {
+            problems.push(Problem {
+                severity: Severity::Error,
+                file: file.to_owned(),
+                line,
+                message: format!("includeIf \"{}\" points to {}, which doesn't exist", condition, entry.value),
+            });
+            continue;
+        }
+
+        let target_level = Some(Level::File(target.display().to_string()));
+        if let Some(email) = git_config_client.get_value("user.email", &target_level)? {
+            if !profiles.values().any(|profile| profile.email == email) {
+                problems.push(Problem {
+                    severity: Severity::Warning,
+                    file: file.to_owned(),
+                    line,
+                    message: format!(
+                        "includeIf \"{}\" sets user.email={}, which doesn't belong to any profile",
+                        condition, email
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+pub fn execute(context: &AppContext, format: OutputFormat) -> Result<()> {
+    let path = context.config_client.path();
+    let file = path.display().to_string();
+    // A missing config file is simply empty
+    let content = fs::read_to_string(path).unwrap_or_default();
+
+    let mut problems = vec![];
+    let maybe_config = match toml::from_str::<Config>(&content) {
+        Ok(config) => Some(config),
+        Err(error) => {
+            problems.push(Problem {
+                severity: Severity::Error,
+                file: file.to_owned(),
+                line: error.span().map(|span| line_of_offset(&content, span.start)),
+                message: error.message().to_owned(),
+            });
+            None
+        }
+    };
+    if let Some(config) = maybe_config.as_ref() {
+        problems.extend(lint_config(config, &content, &file));
+        problems.extend(lint_signing_keys(config, &content, &file));
+    }
+    problems.extend(lint_includes(context, maybe_config.as_ref().unwrap_or(&Config::new()))?);
+
+    match format {
+        OutputFormat::Text if problems.is_empty() => println!("✨ No problems found"),
+        OutputFormat::Text => problems.iter().for_each(|problem| println!("{}", problem)),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&problems)?),
+    }
+
+    let error_count = problems
+        .iter()
+        .filter(|problem| problem.severity == Severity::Error)
+        .count();
+    if error_count > 0 {
+        bail!("Found {} error(s) in the configuration", error_count);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"[profile.base]
+name = "Foo Bar"
+email = "foo@bar.com"
+
+[profile."work"]
+extends = "base"
+email = "not-an-email"
+
+[profile.copy]
+name = "Foo Bar"
+email = "foo@bar.com"
+
+[profile.orphan]
+extends = "missing"
+"#;
+
+    #[test]
+    fn locates_headers_and_fields() {
+        assert_eq!(locate(CONTENT, "base", None), Some(1));
+        assert_eq!(locate(CONTENT, "base", Some("email")), Some(3));
+        assert_eq!(locate(CONTENT, "work", Some("email")), Some(7));
+        // Not written in the table, so the header is used
+        assert_eq!(locate(CONTENT, "work", Some("name")), Some(5));
+        assert_eq!(locate(CONTENT, "unknown", None), None);
+    }
+
+    #[test]
+    fn lints() {
+        let config: Config = toml::from_str(CONTENT).unwrap();
+        let problems = lint_config(&config, CONTENT, "gitprofile.toml");
+        let summary: Vec<(Option<usize>, &str)> = problems
+            .iter()
+            .map(|problem| (problem.line, problem.message.as_str()))
+            .collect();
+
+        assert_eq!(problems.len(), 3);
+        assert!(summary.contains(&(Some(7), "Profile work has an invalid email not-an-email")));
+        assert!(summary.contains(&(Some(9), "Profiles base and copy have the same values")));
+        assert!(summary.contains(&(Some(14), "Profile orphan extends missing, which doesn't exist")));
+    }
+
+    #[test]
+    fn displays_positions() {
+        let problem = Problem {
+            severity: Severity::Warning,
+            file: "gitprofile.toml".to_string(),
+            line: Some(3),
+            message: "Something".to_string(),
+        };
+        assert_eq!(problem.to_string(), "gitprofile.toml:3: warning: Something");
+    }
+}
@@ -0,0 +1,110 @@
+use std::{env, io::{self, Write}, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap_complete::{env::Shells, CompletionCandidate};
+
+use crate::config::{AppConfigClient, Persist};
+
+pub const SHELLS: [&str; 5] = ["bash", "zsh", "fish", "powershell", "elvish"];
+
+/// Environment variable asking the binary to complete its own command line instead of running it
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+const BIN: &str = "git-profile";
+
+// git's bash completion calls `_git_<subcommand>` with the whole `git ...` command line, so the
+// words up to `profile` are replaced with the name of the binary before delegating.
+const BASH_GIT_SUBCOMMAND: &str = r#"
+_git_profile() {
+    local index=1
+    while [[ $index -lt $COMP_CWORD && ${COMP_WORDS[index]} != profile ]]; do
+        ((index++))
+    done
+    local shifted_words=(git-profile "${COMP_WORDS[@]:index+1}")
+    local shifted_cword=$((COMP_CWORD - index))
+    local COMP_WORDS=("${shifted_words[@]}")
+    local COMP_CWORD=$shifted_cword
+    _clap_complete_git_profile git-profile "${COMP_WORDS[COMP_CWORD]}"
+}
+"#;
+
+// zsh's _git has already shifted the words so that `profile` comes first when it calls `_git-profile`.
+const ZSH_GIT_SUBCOMMAND: &str = r#"
+function _git-profile() {
+    words[1]=git-profile
+    _clap_dynamic_completer_git_profile
+}
+"#;
+
+/// Prints the script registering the completions of git-profile, and of `git profile`
+/// where the shell lets git delegate to it, for the given shell
+pub fn execute(shell: &str) -> Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell)
+        .ok_or_else(|| anyhow!("Unsupported shell {}", shell))?;
+    let current_exe = env::current_exe()?;
+
+    let mut stdout = io::stdout();
+    completer.write_registration(COMPLETE_VAR, BIN, BIN, &current_exe.to_string_lossy(), &mut stdout)?;
+    match shell {
+        "bash" => write!(stdout, "{}", BASH_GIT_SUBCOMMAND)?,
+        "zsh" => write!(stdout, "{}", ZSH_GIT_SUBCOMMAND)?,
+        // fish completes `git <subcommand>` with the completions of `git-<subcommand>` by itself
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Lists the profile keys of the config file for the dynamic completion of profile arguments
+pub fn profile_keys() -> Vec<CompletionCandidate> {
+    let client = AppConfigClient::new(config_path_of(env::args().skip_while(|arg| arg != "--")));
+    let Ok(config) = client.load() else {
+        return vec![];
+    };
+
+    config
+        .profile
+        .iter()
+        .map(|(key, profile)| {
+            let help = if profile.is_abstract { "template".to_owned() } else { profile.email.clone() };
+            CompletionCandidate::new(key).help(Some(help.into()))
+        })
+        .collect()
+}
+
+/// Finds the config file given with `-c`/`--config-path` on the command line being completed
+fn config_path_of(args: impl Iterator<Item = String>) -> PathBuf {
+    let args: Vec<String> = args.collect();
+    let given = args.iter().enumerate().find_map(|(index, arg)| match arg.as_str() {
+        "-c" | "--config-path" => args.get(index + 1).cloned(),
+        _ => arg
+            .strip_prefix("--config-path=")
+            .or_else(|| arg.strip_prefix("-c").filter(|value| !value.is_empty()))
+            .map(str::to_owned),
+    });
+
+    given
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(crate::get_default_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split(' ').map(str::to_owned)
+    }
+
+    #[test]
+    fn config_path_is_read_from_the_command_line() {
+        let expected = PathBuf::from("/tmp/profiles.toml");
+        assert_eq!(config_path_of(args("-- git-profile -c /tmp/profiles.toml apply ")), expected);
+        assert_eq!(config_path_of(args("-- git-profile --config-path /tmp/profiles.toml show ")), expected);
+        assert_eq!(config_path_of(args("-- git-profile --config-path=/tmp/profiles.toml edit ")), expected);
+        assert_eq!(config_path_of(args("-- git-profile -c/tmp/profiles.toml remove ")), expected);
+        assert_eq!(config_path_of(args("-- git-profile apply ")), crate::get_default_path());
+    }
+}
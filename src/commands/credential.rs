@@ -0,0 +1,42 @@
+use anyhow::Result;
+use std::io::{self, Read};
+
+use crate::{context::AppContext, credential};
+
+const OPERATIONS: [&str; 3] = ["get", "store", "erase"];
+
+/// Answers git with the username of the profile in use, and lets the helper of that credential
+/// store the password. Unknown remotes get no answer so that git moves on to other helpers.
+pub fn execute(context: &AppContext, operation: &str) -> Result<()> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+
+    // Helpers must ignore the operations they don't know, which git may add later
+    if !OPERATIONS.contains(&operation) {
+        return Ok(());
+    }
+    let mut attributes = credential::parse(&input);
+
+    let config = context.config_client.load()?;
+    let Some(profile_key) = context.git_config_client.get_profile_key(&config, &None)? else {
+        return Ok(());
+    };
+    let (profile, _) = config.resolve_profile(&profile_key)?;
+    let Some(matched) = credential::find(&profile, &attributes) else {
+        return Ok(());
+    };
+
+    attributes.insert("username".to_owned(), matched.username.to_owned());
+    let mut answer = match matched.helper.as_ref() {
+        Some(helper) => credential::delegate(helper, operation, &attributes)?,
+        None => credential::Attributes::new(),
+    };
+
+    if operation == "get" {
+        // The profile decides the account, whatever the helper remembers
+        answer.shift_remove("username");
+        answer.insert("username".to_owned(), matched.username.to_owned());
+        print!("{}", credential::format(&answer));
+    }
+    Ok(())
+}
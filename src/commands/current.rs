@@ -15,7 +15,7 @@ fn generate_output(context: &AppContext, selected_property: &ProfileProperty, ma
     let config = context.config_client.load()?;
 
     if let Some(current_profile_key) = git_config_client.get_profile_key(&config, maybe_level)? {
-        let current_profile = config.profile.get(&current_profile_key).unwrap();
+        let (current_profile, _) = config.resolve_profile(&current_profile_key)?;
         match selected_property {
             ProfileProperty::Name => Ok(Some(current_profile.name.to_owned())),
             ProfileProperty::Email => Ok(Some(current_profile.email.to_owned())),
@@ -29,6 +29,9 @@ fn generate_output(context: &AppContext, selected_property: &ProfileProperty, ma
                 if let Some(signingkey) = current_profile.signingkey.as_ref() {
                     lines.push(format!("user.signingkey={}", signingkey));
                 }
+                if let Some(signingformat) = current_profile.signingformat.as_ref() {
+                    lines.push(format!("gpg.format={}", signingformat.as_str()));
+                }
                 let lines = lines;
                 Ok(Some(lines.join("\n")))
             }
@@ -40,7 +43,7 @@ fn generate_output(context: &AppContext, selected_property: &ProfileProperty, ma
 }
 
 pub fn execute(context: &AppContext, selected_property: &ProfileProperty, maybe_level: &Option<Level>) -> Result<()> {
-    if let Some(output) = generate_output(&context, &selected_property, &maybe_level)? {
+    if let Some(output) = generate_output(context, selected_property, maybe_level)? {
         println!("{}", output);
         Ok(())
     } else {
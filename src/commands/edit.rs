@@ -1,14 +1,14 @@
 use crate::{
     config::{Config, Profile},
-    context::AppContext, validation::email::is_email,
+    context::AppContext, prompt, validation::{email::is_email, signing_key},
 };
 use anyhow::{anyhow, Result};
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input};
 
 fn retrieve_profile_key(config: &Config, supplied_profile_key: &Option<String>) -> Result<String> {
     // If the user supplied the key, we use that here.
     if let Some(profile_key) = supplied_profile_key {
-        if !config.has_profile_key(&profile_key) {
+        if !config.has_profile_key(profile_key) {
             return Err(anyhow!("Profile {} doesn't exist", profile_key));
         }
         return Ok(profile_key.to_owned())
@@ -22,51 +22,44 @@ fn retrieve_profile_key(config: &Config, supplied_profile_key: &Option<String>)
         return Ok(config.profile.first().as_ref().unwrap().0.to_owned())
     }
 
-    let profile_key_options: Vec<String> = Vec::from_iter(config.profile.keys().cloned());
-
-    let selected_index = Select::with_theme(&ColorfulTheme::default())
-        .items(&profile_key_options)
-        .with_prompt("Select which profile to edit")
-        // .default(0)
-        .interact()?;
-
-    Ok(profile_key_options[selected_index].to_owned())
+    let profile_keys: Vec<String> = Vec::from_iter(config.profile.keys().cloned());
+    prompt::select_profile_key(config, &profile_keys, "Select which profile to edit", None)
 }
 
 fn retrieve_profile(config: &Config, profile_key: &str) -> Result<Profile> {
     let target_profile = &config.profile[profile_key];
 
+    // Profiles extending another one can leave fields empty to inherit them,
+    // and templates don't need to be complete nor hold valid emails
+    let allow_empty = target_profile.extends.is_some() || target_profile.is_abstract;
+    let prompt_suffix = match (target_profile.extends.as_ref(), target_profile.is_abstract) {
+        (Some(parent), _) => format!(", empty to inherit from {}", parent),
+        (None, true) => ", may contain {placeholders}".to_owned(),
+        (None, false) => "".to_owned(),
+    };
+
     let name: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enter user name (user.name)")
+        .with_prompt(format!("Enter user name (user.name{})", prompt_suffix))
         .with_initial_text(&target_profile.name)
+        .allow_empty(allow_empty)
         .interact_text()?;
 
     let email: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enter email (user.email)")
+        .with_prompt(format!("Enter email (user.email{})", prompt_suffix))
         .with_initial_text(&target_profile.email)
-        .validate_with(|input: &String| is_email(input))
+        .allow_empty(allow_empty)
+        .validate_with(|input: &String| {
+            if target_profile.is_abstract || (allow_empty && input.is_empty()) {
+                Ok(())
+            } else {
+                is_email(input)
+            }
+        })
         .interact_text()?;
     
-    let maybe_signingkey: Option<String> = if target_profile.signingkey.is_none() {
-        // The profile didn't have a signing key.
-        // Asking the user if it's needed
-        let should_set_signingkey = Confirm::with_theme(&ColorfulTheme::default())
-            .with_prompt("Do you want to set signing key (user.signingkey)")
-            .default(false)
-            .interact()?;
-        if should_set_signingkey {
-            let signingkey = Input::with_theme(&ColorfulTheme::default())
-                .with_prompt("Enter signing key (user.singingkey)")
-                .interact_text()?;
-            Some(signingkey)
-        } else {
-            None
-        }
-    } else {
+    let (maybe_signingkey, signingformat) = if let Some(old_signingkey) = target_profile.signingkey.as_ref() {
         // The profile has signing key set up.
         // The user can choose to retain it or to discard it.
-        let old_signingkey = target_profile.signingkey.as_ref().unwrap();
-
         let signingkey_input: String = Input::with_theme(&ColorfulTheme::default())
             .with_prompt("Enter signing key (user.singingkey)")
             .with_initial_text(old_signingkey)
@@ -75,9 +68,31 @@ fn retrieve_profile(config: &Config, profile_key: &str) -> Result<Profile> {
             .interact_text()?;
 
         if signingkey_input.is_empty() {
-            None
+            (None, None)
+        } else if signingkey_input == *old_signingkey {
+            (Some(signingkey_input), target_profile.signingformat)
+        } else {
+            let signingformat = signing_key::format_of(&signingkey_input);
+            (Some(signingkey_input), Some(signingformat))
+        }
+    } else {
+        // The profile didn't have a signing key.
+        // Asking the user if it's needed
+        let should_set_signingkey = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Do you want to set signing key (user.signingkey)")
+            .default(false)
+            .interact()?;
+        if should_set_signingkey {
+            // Keys are looked up by the effective email, which may be inherited
+            let lookup_email = if email.is_empty() {
+                config.resolve_profile(profile_key)?.0.email
+            } else {
+                email.to_owned()
+            };
+            let (signingkey, signingformat) = prompt::select_signingkey(&lookup_email)?;
+            (Some(signingkey), Some(signingformat))
         } else {
-            Some(signingkey_input.to_owned())
+            (None, None)
         }
     };
 
@@ -85,6 +100,8 @@ fn retrieve_profile(config: &Config, profile_key: &str) -> Result<Profile> {
         name,
         email,
         signingkey: maybe_signingkey,
+        signingformat,
+        ..target_profile.clone()
     };
 
     Ok(new_profile)
@@ -101,7 +118,7 @@ pub fn execute(context: &AppContext, maybe_profile_key: &Option<String>) -> Resu
         // If there's only one profile, we don't need to ask which to select.
         config.profile.first().as_ref().unwrap().0.to_owned()
     } else {
-        retrieve_profile_key(&config, &maybe_profile_key)?
+        retrieve_profile_key(&config, maybe_profile_key)?
     };
 
     let new_profile = retrieve_profile(&config, &profile_key)?;
@@ -110,6 +127,14 @@ pub fn execute(context: &AppContext, maybe_profile_key: &Option<String>) -> Resu
     config.upsert_profile(&profile_key, new_profile);
     let config = config;
 
+    // The email may be inherited, so the key is verified against the resolved profile
+    let (resolved_profile, _) = config.resolve_profile(&profile_key)?;
+    if let (false, Some(signingkey)) = (resolved_profile.is_abstract, resolved_profile.signingkey.as_ref()) {
+        if let Err(error) = signing_key::verify(signingkey, &resolved_profile.email) {
+            println!("⚠️  {}", error);
+        }
+    }
+
     context.config_client.save(&config)?;
 
     println!("✨ Successfully modified {}", profile_key);
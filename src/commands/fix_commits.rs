@@ -0,0 +1,138 @@
+use anyhow::{anyhow, bail, Result};
+use std::process::Command;
+
+use crate::{config::Profile, context::AppContext};
+
+/// Where the original branch is saved before rewriting it
+const BACKUP_REF_PREFIX: &str = "refs/gitprofile/backup/";
+
+/// Runs git with the given arguments, returning its trimmed stdout
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git").args(args).output()?;
+    if !output.status.success() {
+        bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_owned())
+}
+
+/// `-c` options making git commit as the given profile, signing or not
+fn identity_args(profile: &Profile, sign: bool) -> Vec<String> {
+    let mut args = vec![
+        format!("user.name={}", profile.name),
+        format!("user.email={}", profile.email),
+        format!("commit.gpgSign={}", sign),
+    ];
+    if sign {
+        if let Some(signingkey) = profile.signingkey.as_ref() {
+            args.push(format!("user.signingKey={}", signingkey));
+        }
+        if let Some(signingformat) = profile.effective_signingformat() {
+            args.push(format!("gpg.format={}", signingformat.as_str()));
+        }
+    }
+    args.into_iter().flat_map(|arg| ["-c".to_owned(), arg]).collect()
+}
+
+/// Wraps a value in single quotes for the `--exec` command of `git rebase`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+pub fn execute(
+    context: &AppContext,
+    profile_key: &str,
+    maybe_onto: Option<&str>,
+    sign: bool,
+    allow_pushed: bool,
+    force: bool,
+) -> Result<()> {
+    let config = context.config_client.load()?;
+    if !config.has_profile_key(profile_key) {
+        bail!("Profile {} doesn't exist", profile_key);
+    }
+    if config.is_template(profile_key) {
+        bail!("Profile {} is a template", profile_key);
+    }
+    let (profile, _) = config.resolve_profile(profile_key)?;
+    if sign && profile.signingkey.is_none() {
+        bail!("Profile {} has no signing key", profile_key);
+    }
+
+    let upstream = maybe_onto.unwrap_or("@{upstream}");
+    let base = git(&["merge-base", upstream, "HEAD"])
+        .map_err(|_| anyhow!("Can't find where the branch forked from {}. Use --onto <upstream>", upstream))?;
+    let range = format!("{}..HEAD", base);
+
+    let commit_count: usize = git(&["rev-list", "--count", &range])?.parse()?;
+    if commit_count == 0 {
+        println!("✨ No commits to fix");
+        return Ok(());
+    }
+    let unpushed_count: usize = git(&["rev-list", "--count", &range, "--not", "--remotes"])?.parse()?;
+    if unpushed_count < commit_count && !allow_pushed {
+        bail!(
+            "{} commit(s) are already on a remote-tracking branch. Use --allow-pushed to rewrite them anyway",
+            commit_count - unpushed_count
+        );
+    }
+    if !git(&["status", "--porcelain", "--untracked-files=no"])?.is_empty() {
+        bail!("The working tree has uncommitted changes. Commit or stash them first");
+    }
+
+    // Keep the original commits reachable in case the result isn't what was expected
+    let branch = git(&["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .map_err(|_| anyhow!("HEAD is detached. Check out the branch to fix first"))?;
+    let backup_ref = format!("{}{}", BACKUP_REF_PREFIX, branch);
+    if !force && git(&["rev-parse", "--verify", "--quiet", &backup_ref]).is_ok() {
+        bail!("{} already holds a backup of {}. Use --force to replace it", backup_ref, branch);
+    }
+    git(&["update-ref", &backup_ref, "HEAD"])?;
+    println!("Backed up the original commits to {}", backup_ref);
+
+    let author = format!("{} <{}>", profile.name, profile.email);
+    let amend = format!("git commit --amend --no-edit --no-verify --allow-empty --author={}", shell_quote(&author));
+    let status = Command::new("git")
+        .args(identity_args(&profile, sign))
+        .args(["rebase", "--rebase-merges", "--exec", &amend, &base])
+        .status()?;
+    if !status.success() {
+        bail!("git rebase failed. Run `git rebase --abort` and `git reset --hard {}` to go back", backup_ref);
+    }
+
+    println!("✨ Rewrote {} commit(s) as {}", commit_count, profile_key);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SigningFormat;
+
+    #[test]
+    fn builds_identity_args() {
+        let profile = Profile {
+            name: "Foo Bar".to_string(),
+            email: "foo@bar.com".to_string(),
+            signingkey: Some("~/.ssh/foo.pub".to_string()),
+            signingformat: Some(SigningFormat::Ssh),
+            ..Default::default()
+        };
+        assert_eq!(
+            identity_args(&profile, false),
+            vec!["-c", "user.name=Foo Bar", "-c", "user.email=foo@bar.com", "-c", "commit.gpgSign=false"]
+        );
+        assert_eq!(
+            identity_args(&profile, true)[4..],
+            ["-c", "commit.gpgSign=true", "-c", "user.signingKey=~/.ssh/foo.pub", "-c", "gpg.format=ssh"]
+        );
+
+        let inferred_profile = Profile {
+            signingformat: None,
+            ..profile
+        };
+        assert_eq!(
+            identity_args(&inferred_profile, true)[4..],
+            ["-c", "commit.gpgSign=true", "-c", "user.signingKey=~/.ssh/foo.pub", "-c", "gpg.format=ssh"]
+        );
+    }
+}
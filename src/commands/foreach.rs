@@ -0,0 +1,109 @@
+use anyhow::{anyhow, bail, Result};
+use std::path::Path;
+
+use crate::{
+    commands::apply,
+    config::Config,
+    context::AppContext,
+    discovery,
+    git::Level,
+    rules,
+};
+
+/// Which profile each repository gets
+pub enum Selection {
+    /// The one of the first matching rule
+    RuleBased,
+    Profile(String),
+}
+
+enum Outcome {
+    Changed(String),
+    Unchanged(String),
+    Skipped(String),
+    Failed(String),
+}
+
+impl Outcome {
+    fn label(&self) -> &'static str {
+        match self {
+            Outcome::Changed(_) => "changed",
+            Outcome::Unchanged(_) => "unchanged",
+            Outcome::Skipped(_) => "skipped",
+            Outcome::Failed(_) => "failed",
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            Outcome::Changed(detail)
+            | Outcome::Unchanged(detail)
+            | Outcome::Skipped(detail)
+            | Outcome::Failed(detail) => detail,
+        }
+    }
+}
+
+/// Applies the selected profile to the repository the git client of the context runs in
+fn process(context: &AppContext, config: &Config, selection: &Selection, dry_run: bool) -> Result<Outcome> {
+    let local = Some(Level::Local);
+    let profile_key = match selection {
+        Selection::Profile(profile_key) => profile_key.to_owned(),
+        Selection::RuleBased => {
+            let git_dir = context.git_config_client.git_dir()?.ok_or_else(|| anyhow!("Not a git repository"))?;
+            match rules::rule_for(config, &git_dir) {
+                Some(rule) => rule.profile.to_owned(),
+                None => return Ok(Outcome::Skipped("no matching rule".to_owned())),
+            }
+        }
+    };
+
+    let current = context.git_config_client.get_profile_key(config, &local)?;
+    if current.as_ref() == Some(&profile_key) {
+        return Ok(Outcome::Unchanged(profile_key));
+    }
+    let change = format!("{} -> {}", current.as_deref().unwrap_or("-"), profile_key);
+    if !dry_run {
+        apply::apply(context, &profile_key, &local, false)?;
+    }
+    Ok(Outcome::Changed(change))
+}
+
+pub fn execute(context: &AppContext, root: &Path, selection: &Selection, dry_run: bool) -> Result<()> {
+    let config = context.config_client.load()?;
+    if let Selection::Profile(profile_key) = selection {
+        if !config.has_profile_key(profile_key) {
+            bail!("Profile {} doesn't exist", profile_key);
+        }
+    }
+
+    let root = std::path::absolute(root)?;
+    let repositories = discovery::find_repositories(&root)?;
+
+    // Git and the policies of the repositories are read through git running in each of them
+    let mut rows = vec![];
+    for repository in repositories.iter() {
+        let outcome = process(&context.in_repository(repository), &config, selection, dry_run)
+            .unwrap_or_else(|error| Outcome::Failed(error.to_string()));
+        rows.push((repository.display().to_string(), outcome));
+    }
+
+    let width = rows.iter().map(|(path, _)| path.len()).max().unwrap_or(0);
+    for (path, outcome) in rows.iter() {
+        println!("{:<width$}  {:<9}  {}", path, outcome.label(), outcome.detail(), width = width);
+    }
+    let count = |label: &str| rows.iter().filter(|(_, outcome)| outcome.label() == label).count();
+    println!(
+        "{}{} changed, {} unchanged, {} skipped, {} failed",
+        if dry_run { "(dry run) " } else { "" },
+        count("changed"),
+        count("unchanged"),
+        count("skipped"),
+        count("failed")
+    );
+
+    if count("failed") > 0 {
+        bail!("Failed to apply a profile to {} repositories", count("failed"));
+    }
+    Ok(())
+}
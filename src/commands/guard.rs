@@ -0,0 +1,210 @@
+use anyhow::{anyhow, bail, Result};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    commands::pair,
+    context::AppContext,
+    git::{self, Level},
+    paths::next_to_config,
+};
+
+/// Hooks installed by the guard. Since core.hooksPath replaces the whole hooks directory, each one
+/// forwards to the hook it replaced. Only the client-side hooks repositories commonly use are
+/// covered, hooks run on every ref update or index write such as reference-transaction aren't.
+/// https://git-scm.com/docs/githooks
+pub const HOOK_NAMES: [&str; 13] = [
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "post-rewrite",
+];
+
+/// Hooks that also check the identity, after the hook they replaced
+pub const CHECKED_HOOK_NAMES: [&str; 2] = ["pre-commit", "pre-push"];
+
+/// Set at the level the guard is installed at. The hooks are also installed while pairing, see
+/// `git profile pair`, but only check the identity where the guard was asked for.
+pub const GUARD: &str = "gitprofile.guard";
+pub const HOOKS_PATH: &str = "core.hooksPath";
+/// The core.hooksPath replaced by the guard, which its hooks chain to
+pub const PREVIOUS_HOOKS_PATH: &str = "gitprofile.previousHooksPath";
+
+/// Directory of the global hooks, next to the config file
+const GLOBAL_HOOKS_DIR: &str = "hooks";
+/// Directory of the hooks of a single repository, inside its git directory
+const LOCAL_HOOKS_DIR: &str = "gitprofile-hooks";
+
+/// Wraps a value in single quotes for `sh`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn script(config_path: &Path, hook_name: &str) -> String {
+    let config_path = std::path::absolute(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+    // Hooks that only forward exit right away when there is nothing to forward to, as
+    // chained_hooks_dir would find in `git profile hook`
+    let forwards_only = !CHECKED_HOOK_NAMES.contains(&hook_name) && hook_name != pair::TRAILERS_HOOK_NAME;
+    let check = if forwards_only {
+        format!(
+            "hooks_dir=$(git config --path {}) || hooks_dir=\"$(git rev-parse --git-common-dir)/hooks\"\n\
+             [ -x \"$hooks_dir/{}\" ] || exit 0\n",
+            PREVIOUS_HOOKS_PATH, hook_name
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        "#!/bin/sh\n\
+         # Installed by git-profile. Run `git profile guard uninstall` to remove it.\n\
+         {}\
+         exec git profile -c {} hook {} \"$@\"\n",
+        check,
+        shell_quote(&config_path.display().to_string()),
+        hook_name
+    )
+}
+
+/// Where the guard is installed for the given scope
+fn target(context: &AppContext, global: bool) -> Result<(Option<Level>, PathBuf)> {
+    if global {
+        let hooks_dir = next_to_config(context.config_client.path(), GLOBAL_HOOKS_DIR);
+        return Ok((Some(Level::Global), hooks_dir));
+    }
+    let git_dir = git::git_common_dir()?.ok_or_else(|| anyhow!("Not in a git repository. Use --global instead"))?;
+    Ok((Some(Level::Local), git_dir.join(LOCAL_HOOKS_DIR)))
+}
+
+/// The core.hooksPath set by the levels below the scope, which git uses when the scope sets none
+fn inherited_hooks_path(context: &AppContext, global: bool) -> Result<Option<String>> {
+    let git_config_client = context.git_config_client.as_ref();
+    let mut maybe_hooks_path = git_config_client.get_value(HOOKS_PATH, &Some(Level::System))?;
+    if !global {
+        maybe_hooks_path = git_config_client.get_value(HOOKS_PATH, &Some(Level::Global))?.or(maybe_hooks_path);
+    }
+    Ok(maybe_hooks_path)
+}
+
+/// Points core.hooksPath of the given scope to hooks calling `git profile hook`
+pub fn install_hooks(context: &AppContext, global: bool) -> Result<PathBuf> {
+    let git_config_client = context.git_config_client.as_ref();
+    let (level, hooks_dir) = target(context, global)?;
+    let hooks_path = hooks_dir.display().to_string();
+
+    fs::create_dir_all(&hooks_dir)?;
+    for hook_name in HOOK_NAMES {
+        let hook_path = hooks_dir.join(hook_name);
+        fs::write(&hook_path, script(context.config_client.path(), hook_name))?;
+        #[cfg(unix)]
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Chain to the hooks git would have run, wherever they were set, except for the global guard
+    // whose hooks would run these again. Reinstalling must not lose the hooks path it replaced.
+    let global_hooks_path = target(context, true)?.1.display().to_string();
+    let effective = git_config_client.get_value(HOOKS_PATH, &level)?.or(inherited_hooks_path(context, global)?);
+    let maybe_previous = match effective {
+        Some(hooks_path) if !global && hooks_path == global_hooks_path => {
+            git_config_client.get_value(PREVIOUS_HOOKS_PATH, &Some(Level::Global))?
+        }
+        maybe_hooks_path => maybe_hooks_path,
+    };
+    if let Some(previous) = maybe_previous.filter(|previous| previous != &hooks_path) {
+        git_config_client.set_value(PREVIOUS_HOOKS_PATH, &previous, &level)?;
+    }
+    git_config_client.set_value(HOOKS_PATH, &hooks_path, &level)?;
+    Ok(hooks_dir)
+}
+
+/// Removes the hooks of the given scope and restores the core.hooksPath they replaced
+pub fn uninstall_hooks(context: &AppContext, global: bool) -> Result<()> {
+    let git_config_client = context.git_config_client.as_ref();
+    let (level, hooks_dir) = target(context, global)?;
+
+    if git_config_client.get_value(HOOKS_PATH, &level)? != Some(hooks_dir.display().to_string()) {
+        return Ok(());
+    }
+    // A hooks path inherited from the levels below applies again once unset
+    let inherited = inherited_hooks_path(context, global)?;
+    match git_config_client.get_value(PREVIOUS_HOOKS_PATH, &level)? {
+        Some(previous) if Some(&previous) != inherited.as_ref() => {
+            git_config_client.set_value(HOOKS_PATH, &previous, &level)?
+        }
+        _ => git_config_client.unset(HOOKS_PATH, &level)?,
+    }
+    git_config_client.unset(PREVIOUS_HOOKS_PATH, &level)?;
+
+    for hook_name in HOOK_NAMES {
+        fs::remove_file(hooks_dir.join(hook_name)).ok();
+    }
+    // Only removed if nothing else was put in there
+    fs::remove_dir(&hooks_dir).ok();
+    Ok(())
+}
+
+/// Whether the guard was installed for the given scope
+pub fn is_installed(context: &AppContext, global: bool) -> Result<bool> {
+    let (level, _) = target(context, global)?;
+    Ok(context.git_config_client.get_value(GUARD, &level)?.is_some())
+}
+
+pub fn install(context: &AppContext, global: bool) -> Result<()> {
+    let hooks_dir = install_hooks(context, global)?;
+    let (level, _) = target(context, global)?;
+    context.git_config_client.set_value(GUARD, "true", &level)?;
+
+    println!("✨ Installed the guard in {}", hooks_dir.display());
+    Ok(())
+}
+
+pub fn uninstall(context: &AppContext, global: bool) -> Result<()> {
+    if !is_installed(context, global)? {
+        bail!("The guard isn't installed{}", if global { " globally" } else { " in this repository" });
+    }
+    let (level, _) = target(context, global)?;
+    context.git_config_client.unset(GUARD, &level)?;
+
+    // The hooks of the repository also add the trailers of the pairing session
+    if global || !pair::is_pairing(context)? {
+        uninstall_hooks(context, global)?;
+    }
+
+    println!("✨ Uninstalled the guard");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_forwarding_scripts() {
+        assert_eq!(
+            script(Path::new("/home/o'neil/gitprofile.toml"), "pre-push"),
+            "#!/bin/sh\n\
+             # Installed by git-profile. Run `git profile guard uninstall` to remove it.\n\
+             exec git profile -c '/home/o'\\''neil/gitprofile.toml' hook pre-push \"$@\"\n"
+        );
+        assert_eq!(
+            script(Path::new("/gitprofile.toml"), "post-checkout"),
+            "#!/bin/sh\n\
+             # Installed by git-profile. Run `git profile guard uninstall` to remove it.\n\
+             hooks_dir=$(git config --path gitprofile.previousHooksPath) || hooks_dir=\"$(git rev-parse --git-common-dir)/hooks\"\n\
+             [ -x \"$hooks_dir/post-checkout\" ] || exit 0\n\
+             exec git profile -c '/gitprofile.toml' hook post-checkout \"$@\"\n"
+        );
+    }
+}
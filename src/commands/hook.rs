@@ -0,0 +1,123 @@
+use anyhow::{anyhow, bail, Result};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    commands::{
+        guard::{CHECKED_HOOK_NAMES, GUARD, HOOKS_PATH, PREVIOUS_HOOKS_PATH},
+        pair,
+    },
+    context::AppContext,
+    git,
+    paths::expand_home,
+    policy::Policy,
+    rules,
+};
+
+/// Fails when the identity git is about to use doesn't belong to the expected profile,
+/// or when that profile violates the policy of the repository
+fn check_identity(context: &AppContext) -> Result<()> {
+    let git_config_client = context.git_config_client.as_ref();
+    let config = context.config_client.load()?;
+
+    let identity = git_config_client
+        .get(&None)?
+        .ok_or_else(|| anyhow!("No identity is configured. Run `git profile apply <profile>` first"))?;
+    let profile_key = git_config_client.get_profile_key(&config, &None)?.ok_or_else(|| {
+        anyhow!(
+            "{} <{}> doesn't match any profile. Run `git profile apply <profile>` first",
+            identity.name,
+            identity.email
+        )
+    })?;
+
+    if let Some(policy) = Policy::current(git_config_client)? {
+        let (profile, _) = config.resolve_profile(&profile_key)?;
+        policy.enforce(&profile_key, &profile, false)?;
+    }
+
+    if let Some(git_dir) = git::git_dir()? {
+        if let Some(rule) = rules::rule_for(&config, &git_dir) {
+            if rule.profile != profile_key {
+                bail!(
+                    "This repository is expected to use profile {} (rule {}), but {} is in use. Run `git profile apply {}`",
+                    rule.profile,
+                    rule.gitdir,
+                    profile_key,
+                    rule.profile
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The hooks the guard replaced: the previous core.hooksPath if any, or the hooks of the repository
+fn chained_hooks_dir(context: &AppContext) -> Result<Option<PathBuf>> {
+    let git_config_client = context.git_config_client.as_ref();
+    let hooks_dir = match git_config_client.get_value(PREVIOUS_HOOKS_PATH, &None)? {
+        Some(previous) => expand_home(&previous),
+        None => match git::git_common_dir()? {
+            Some(git_dir) => git_dir.join("hooks"),
+            None => return Ok(None),
+        },
+    };
+
+    // Never call ourselves again
+    let current = git_config_client.get_value(HOOKS_PATH, &None)?.map(|path| expand_home(&path));
+    let is_current = current.is_some_and(|current| {
+        fs::canonicalize(&current).ok().is_some_and(|current| fs::canonicalize(&hooks_dir).ok() == Some(current))
+    });
+    Ok(if is_current { None } else { Some(hooks_dir) })
+}
+
+/// git only runs hooks with an executable bit
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+/// There is no executable bit, git runs any hook file
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+/// Runs the hook the guard replaced, if there is one
+fn run_chained_hook(context: &AppContext, hook_name: &str, args: &[String]) -> Result<()> {
+    let Some(hooks_dir) = chained_hooks_dir(context)? else {
+        return Ok(());
+    };
+    let hook_path = hooks_dir.join(hook_name);
+    let is_hook = fs::metadata(&hook_path).is_ok_and(|metadata| metadata.is_file() && is_executable(&metadata));
+    if !is_hook {
+        return Ok(());
+    }
+
+    // stdin is inherited, pre-push reads the pushed refs from it
+    let status = Command::new(&hook_path).args(args).status()?;
+    if !status.success() {
+        bail!("{} failed", hook_path.display());
+    }
+    Ok(())
+}
+
+pub fn execute(context: &AppContext, hook_name: &str, args: &[String]) -> Result<()> {
+    run_chained_hook(context, hook_name, args)?;
+
+    if hook_name == pair::TRAILERS_HOOK_NAME {
+        if let Some(message_path) = args.first() {
+            pair::add_trailers(context, Path::new(message_path))?;
+        }
+    }
+    let is_guarded = context.git_config_client.get_value(GUARD, &None)?.is_some();
+    if is_guarded && CHECKED_HOOK_NAMES.contains(&hook_name) {
+        check_identity(context)?;
+    }
+    Ok(())
+}
@@ -1,22 +1,26 @@
 use crate::{
+    config::{Profile, Rule, SigningFormat},
     context::AppContext,
-    git::{Level},
-    validation::{self},
+    git::{find_profile_key, identity_levels, Level},
+    paths::resolve_config_path,
+    prompt,
 };
 use anyhow::{bail, Result};
-use dialoguer::{theme::ColorfulTheme, Input};
+use dialoguer::{theme::ColorfulTheme, MultiSelect};
+use indexmap::IndexMap;
+use std::{path::Path, process::Command};
 
 pub fn execute(context: &AppContext, maybe_level: &Option<Level>) -> Result<()> {
     let config = context.config_client.load()?;
     if let Some(profile_key) = context
         .git_config_client
-        .get_profile_key(&config, &maybe_level)?
+        .get_profile_key(&config, maybe_level)?
     {
         // Already imported
         bail!("Already imported as {}", profile_key);
     }
 
-    let maybe_profile = context.git_config_client.get(&maybe_level)?;
+    let maybe_profile = context.git_config_client.get(maybe_level)?;
 
     if maybe_profile.is_none() {
         bail!("Can't import anything as git config values were not found.");
@@ -31,13 +35,7 @@ pub fn execute(context: &AppContext, maybe_level: &Option<Level>) -> Result<()>
         println!("user.signingkey={}", signingkey);
     }
 
-    let profile_name: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enter profile name")
-        .validate_with(|input: &String| -> Result<()> {
-            validation::profile_key::no_duplicates(input, &config.profile)?;
-            Ok(())
-        })
-        .interact_text()?;
+    let profile_name = prompt::input_profile_key(&config.profile)?;
 
     let mut config = config;
     config.upsert_profile(&profile_name, profile);
@@ -51,3 +49,291 @@ pub fn execute(context: &AppContext, maybe_level: &Option<Level>) -> Result<()>
     );
     Ok(())
 }
+
+/// An identity found somewhere, along with where it was found
+pub struct Candidate {
+    pub profile: Profile,
+    pub description: String,
+}
+
+/// Lets the user pick several identities and name each of them as a new profile.
+/// Identities matching an existing profile are skipped.
+fn import_candidates(context: &AppContext, candidates: Vec<Candidate>) -> Result<()> {
+    let mut config = context.config_client.load()?;
+    let profiles = config.resolved_profiles()?;
+
+    let mut new_candidates = vec![];
+    for candidate in candidates {
+        match find_profile_key(&profiles, &candidate.profile) {
+            Some(profile_key) => println!("Skipped {}, already imported as {}", candidate.description, profile_key),
+            None => new_candidates.push(candidate),
+        }
+    }
+    if new_candidates.is_empty() {
+        println!("Nothing new to import");
+        return Ok(());
+    }
+
+    let items: Vec<&str> = new_candidates
+        .iter()
+        .map(|candidate| candidate.description.as_str())
+        .collect();
+    let selected_indexes = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Select the identities to import (space to select, enter to confirm)")
+        .items(&items)
+        .interact()?;
+
+    let mut imported = vec![];
+    for index in selected_indexes {
+        let candidate = &new_candidates[index];
+        println!("{}", candidate.description);
+        let profile_key = prompt::input_profile_key(&config.profile)?;
+        config.upsert_profile(&profile_key, candidate.profile.clone());
+        imported.push(profile_key);
+    }
+
+    context.config_client.save(&config)?;
+    imported
+        .iter()
+        .for_each(|profile_key| println!("✨ Successfully imported a git user as {}", profile_key));
+    Ok(())
+}
+
+/// Collects the distinct identities of `git log` output, most frequent first
+fn rank_identities(log_output: &str) -> Vec<(Profile, usize)> {
+    let mut counts: IndexMap<(String, String, Option<String>), usize> = IndexMap::new();
+    for record in log_output.split('\x1e').map(str::trim).filter(|record| !record.is_empty()) {
+        let fields: Vec<&str> = record.split('\x1f').collect();
+        let [name, email, signingkey] = fields.as_slice() else {
+            continue;
+        };
+        // SSH signatures report a fingerprint, which can't be used as user.signingkey
+        let signingkey = Some(signingkey.to_string()).filter(|key| !key.is_empty() && !key.starts_with("SHA256:"));
+        *counts.entry((name.to_string(), email.to_string(), signingkey)).or_default() += 1;
+    }
+
+    let mut ranked: Vec<(Profile, usize)> = counts
+        .into_iter()
+        .map(|((name, email, signingkey), count)| {
+            let profile = Profile {
+                name,
+                email,
+                signingkey,
+                ..Default::default()
+            };
+            (profile, count)
+        })
+        .collect();
+    // Stable, so ties keep the order of the history
+    ranked.sort_by(|(_, a), (_, b)| b.cmp(a));
+    ranked
+}
+
+pub fn execute_from_log(context: &AppContext, repository: &Path) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(["log", "--format=%an%x1f%ae%x1f%GK%x1e"])
+        .output()?;
+    if !output.status.success() {
+        bail!("Can't read the history of {}: {}", repository.display(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let candidates = rank_identities(&String::from_utf8(output.stdout)?)
+        .into_iter()
+        .map(|(profile, count)| {
+            let signing = match profile.signingkey.as_ref() {
+                Some(signingkey) => format!(", signed with {}", signingkey),
+                None => String::new(),
+            };
+            let description = format!("{} <{}> ({} commits{})", profile.name, profile.email, count, signing);
+            Candidate { profile, description }
+        })
+        .collect();
+    import_candidates(context, candidates)
+}
+
+pub fn execute_all_levels(context: &AppContext) -> Result<()> {
+    let git_config_client = context.git_config_client.as_ref();
+
+    // The same identity may be set at several levels
+    let mut sources: Vec<(Profile, Vec<&str>)> = vec![];
+    for (label, level) in identity_levels(git_config_client)? {
+        let Some(profile) = git_config_client.get(&Some(level))? else {
+            continue;
+        };
+        match sources.iter_mut().find(|(known, _)| known.same_identity(&profile)) {
+            Some((_, labels)) => labels.push(label),
+            None => sources.push((profile, vec![label])),
+        }
+    }
+    if sources.is_empty() {
+        bail!("Can't import anything as git config values were not found.");
+    }
+
+    let candidates = sources
+        .into_iter()
+        .map(|(profile, labels)| {
+            let signing = match profile.signingkey.as_ref() {
+                Some(signingkey) => format!(", signed with {}", signingkey),
+                None => String::new(),
+            };
+            let description = format!("{} <{}> ({}{})", profile.name, profile.email, labels.join(", "), signing);
+            Candidate { profile, description }
+        })
+        .collect();
+    import_candidates(context, candidates)
+}
+
+/// Converts an `includeIf` condition to the gitdir pattern of a rule, and whether it ignores case.
+/// Only `gitdir:` and `gitdir/i:` conditions can be converted, `./` being relative to the file holding them.
+fn gitdir_of(condition: &str, config_file: &Path) -> Option<(String, bool)> {
+    let (pattern, ignore_case) = match condition.strip_prefix("gitdir:") {
+        Some(pattern) => (pattern, false),
+        None => (condition.strip_prefix("gitdir/i:")?, true),
+    };
+    let gitdir = match pattern.strip_prefix("./") {
+        Some(relative) => {
+            let dir = config_file.parent()?;
+            let mut gitdir = dir.join(relative).display().to_string();
+            if pattern.ends_with('/') && !gitdir.ends_with('/') {
+                gitdir.push('/');
+            }
+            gitdir
+        }
+        None => pattern.to_owned(),
+    };
+    Some((gitdir, ignore_case))
+}
+
+pub fn execute_from_includes(context: &AppContext) -> Result<()> {
+    let git_config_client = context.git_config_client.as_ref();
+    let global = Some(Level::Global);
+    let mut config = context.config_client.load()?;
+
+    // Included files usually only override the email, the rest comes from the global config
+    let global_profile = git_config_client.get(&global)?;
+    let global_name = git_config_client.get_name(&global)?;
+
+    let entries = git_config_client.get_regexp(r"^includeif\..*\.path$", &global)?;
+    if entries.is_empty() {
+        bail!("No includeIf found in the global git config");
+    }
+
+    let mut imported_count = 0;
+    for entry in entries {
+        let config_file = Path::new(entry.origin.strip_prefix("file:").unwrap_or(&entry.origin));
+        let condition = entry
+            .key
+            .strip_prefix("includeif.")
+            .and_then(|key| key.strip_suffix(".path"))
+            .unwrap_or(&entry.key);
+        let Some((gitdir, ignore_case)) = gitdir_of(condition, config_file) else {
+            println!("Skipped includeIf \"{}\": only gitdir conditions can become rules", condition);
+            continue;
+        };
+
+        let target = resolve_config_path(&entry.value, config_file);
+        let target_level = Some(Level::File(target.display().to_string()));
+        let Some(email) = git_config_client.get_email(&target_level)? else {
+            println!("Skipped includeIf \"{}\": {} sets no user.email", condition, entry.value);
+            continue;
+        };
+        let Some(name) = git_config_client.get_name(&target_level)?.or(global_name.clone()) else {
+            println!("Skipped includeIf \"{}\": no user.name for {}", condition, email);
+            continue;
+        };
+        let signingkey = git_config_client
+            .get_signingkey(&target_level)?
+            .or(global_profile.as_ref().and_then(|profile| profile.signingkey.clone()));
+        let signingformat = git_config_client
+            .get_value("gpg.format", &target_level)?
+            .and_then(|format| SigningFormat::parse(&format));
+        let profile = Profile {
+            name,
+            email,
+            signingkey,
+            signingformat,
+            ..Default::default()
+        };
+
+        let profile_key = match find_profile_key(&config.resolved_profiles()?, &profile) {
+            Some(profile_key) => profile_key,
+            None => {
+                println!("Found {} <{}> in {} for {}", profile.name, profile.email, entry.value, gitdir);
+                let profile_key = prompt::input_profile_key(&config.profile)?;
+                config.upsert_profile(&profile_key, profile);
+                imported_count += 1;
+                profile_key
+            }
+        };
+
+        let rule = Rule {
+            gitdir,
+            ignore_case,
+            profile: profile_key,
+        };
+        if !config.rule.contains(&rule) {
+            let case = if rule.ignore_case { " (ignoring case)" } else { "" };
+            println!("Added a rule applying {} to {}{}", rule.profile, rule.gitdir, case);
+            config.rule.push(rule);
+        }
+    }
+
+    context.config_client.save(&config)?;
+    println!("✨ Successfully imported {} profile(s) from includeIf entries", imported_count);
+    println!("The includeIf entries are left in place. Remove them from the global git config once you don't need them anymore");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{paths::expand_home, rules};
+
+    #[test]
+    fn ranks_identities_by_frequency() {
+        let log_output = "Foo\x1ffoo@home.com\x1f\x1e\n\
+                          Foo\x1ffoo@work.com\x1fABCD1234\x1e\n\
+                          Foo\x1ffoo@work.com\x1fABCD1234\x1e\n\
+                          Foo\x1ffoo@work.com\x1f\x1e\n\
+                          Foo\x1ffoo@oss.com\x1fSHA256:abc\x1e\n";
+        let identities = rank_identities(log_output);
+        let ranked: Vec<(&str, Option<&str>, usize)> = identities
+            .iter()
+            .map(|(profile, count)| (profile.email.as_str(), profile.signingkey.as_deref(), *count))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("foo@work.com", Some("ABCD1234"), 2),
+                ("foo@home.com", None, 1),
+                ("foo@work.com", None, 1),
+                ("foo@oss.com", None, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_gitdir_conditions() {
+        let config_file = Path::new("/home/me/.gitconfig");
+        let rule_of = |condition: &str| gitdir_of(condition, config_file);
+        assert_eq!(rule_of("gitdir:~/work/"), Some(("~/work/".to_owned(), false)));
+        assert_eq!(rule_of("gitdir/i:/src/Work/"), Some(("/src/Work/".to_owned(), true)));
+        assert_eq!(rule_of("gitdir:./work/"), Some(("/home/me/work/".to_owned(), false)));
+        assert_eq!(rule_of("onbranch:main"), None);
+    }
+
+    #[test]
+    fn converted_rules_match_what_the_conditions_matched() {
+        let config_file = Path::new("/home/me/.gitconfig");
+        let matches = |condition: &str, git_dir: &Path| {
+            let (gitdir, ignore_case) = gitdir_of(condition, config_file).unwrap();
+            rules::matches(&gitdir, ignore_case, git_dir)
+        };
+        assert!(matches("gitdir:~/work/", &expand_home("~/work/app/.git")));
+        assert!(matches("gitdir:./work/", Path::new("/home/me/work/app/.git")));
+        assert!(matches("gitdir/i:/src/Work/", Path::new("/src/work/app/.git")));
+        assert!(!matches("gitdir:/src/Work/", Path::new("/src/work/app/.git")));
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    allowed_signers, config::SigningFormat, context::AppContext, paths::next_to_config,
+    validation::profile_key,
+};
+
+/// Directory where the generated keys are stored, next to the config file
+pub const KEYS_DIR: &str = "keys";
+
+/// What the generated key is recorded as in the profile
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum KeyUsage {
+    /// The signing key of the commits
+    #[default]
+    Signing,
+    /// The SSH identity used to push and fetch
+    Identity,
+    /// Both of them
+    Both,
+}
+
+/// Where the private key of the given profile is generated
+pub fn key_path_for(context: &AppContext, profile_key: &str) -> Result<PathBuf> {
+    profile_key::is_file_name(profile_key)?;
+    Ok(next_to_config(context.config_client.path(), KEYS_DIR).join(profile_key))
+}
+
+fn public_key_path_of(key_path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.pub", key_path.display()))
+}
+
+pub fn execute(context: &AppContext, profile_key: &str, key_type: &str, usage: KeyUsage, force: bool) -> Result<()> {
+    let config = context.config_client.load()?;
+
+    if !config.has_profile_key(profile_key) {
+        bail!("Profile {} doesn't exist", profile_key);
+    }
+    if config.is_template(profile_key) {
+        bail!("Profile {} is a template", profile_key);
+    }
+
+    let key_path = key_path_for(context, profile_key)?;
+    let public_key_path = public_key_path_of(&key_path);
+    if (key_path.exists() || public_key_path.exists()) && !force {
+        bail!("{} already exists. Use --force to overwrite it", key_path.display());
+    }
+    if let Some(keys_dir) = key_path.parent() {
+        fs::create_dir_all(keys_dir)?;
+    }
+
+    // The new pair is generated aside and only replaces the existing one once ssh-keygen succeeded,
+    // so that a failure or an interrupted passphrase prompt doesn't lose the old key
+    let temporary_key_path = key_path.with_file_name(format!(".{}.new", profile_key));
+    let temporary_public_key_path = public_key_path_of(&temporary_key_path);
+    fs::remove_file(&temporary_key_path).ok();
+    fs::remove_file(&temporary_public_key_path).ok();
+
+    let (profile, _) = config.resolve_profile(profile_key)?;
+    // ssh-keygen asks for the passphrase itself
+    let status = Command::new("ssh-keygen")
+        .args(["-t", key_type, "-C", &profile.email, "-f"])
+        .arg(&temporary_key_path)
+        .status();
+    if !status.as_ref().is_ok_and(|status| status.success()) {
+        fs::remove_file(&temporary_key_path).ok();
+        fs::remove_file(&temporary_public_key_path).ok();
+        status?;
+        bail!("ssh-keygen failed");
+    }
+    fs::rename(&temporary_key_path, &key_path)?;
+    fs::rename(&temporary_public_key_path, &public_key_path)?;
+
+    let mut config = config;
+    let target_profile = config.profile.get_mut(profile_key).unwrap();
+    if usage != KeyUsage::Identity {
+        target_profile.signingkey = Some(public_key_path.display().to_string());
+        target_profile.signingformat = Some(SigningFormat::Ssh);
+    }
+    if usage != KeyUsage::Signing {
+        target_profile.ssh_identity = Some(key_path.display().to_string());
+    }
+    let config = config;
+
+    context.config_client.save(&config)?;
+    if usage != KeyUsage::Identity {
+        allowed_signers::write(&config, &allowed_signers::path_for(context.config_client.path()))?;
+    }
+
+    println!("✨ Generated a new key for {}. Upload the following public key to your git hosting service:", profile_key);
+    println!("{}", fs::read_to_string(&public_key_path)?.trim());
+    Ok(())
+}
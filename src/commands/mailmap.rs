@@ -0,0 +1,19 @@
+use anyhow::{anyhow, Result};
+
+use crate::{context::AppContext, git, mailmap};
+
+pub fn execute(context: &AppContext, write: bool) -> Result<()> {
+    let config = context.config_client.load()?;
+
+    if !write {
+        println!("{}", mailmap::render(&config)?);
+        return Ok(());
+    }
+
+    let work_tree = git::work_tree()?.ok_or_else(|| anyhow!("Not in a git repository"))?;
+    let path = work_tree.join(mailmap::FILE_NAME);
+    let entry_count = mailmap::write(&config, &path)?;
+
+    println!("✨ Wrote {} entries to {}", entry_count, path.display());
+    Ok(())
+}
@@ -1,10 +1,28 @@
 pub mod apply;
+pub mod audit_commits;
+pub mod check;
+pub mod completions;
 pub mod config_dump;
+pub mod credential;
 pub mod current;
 pub mod edit;
+pub mod fix_commits;
+pub mod foreach;
+pub mod guard;
+pub mod hook;
 pub mod list;
+pub mod mailmap;
 pub mod import;
+pub mod keygen;
 pub mod new;
+pub mod pair;
 pub mod remove;
+pub mod scan;
 pub mod show;
+pub mod ssh_config;
+pub mod status;
+pub mod strict;
+pub mod sync;
+pub mod teammate;
+pub mod tui;
 pub mod rename;
@@ -1,56 +1,134 @@
-use crate::{config::{Profile, Config}, context::AppContext, git::find_profile_key, validation::{self, email::is_email}, commands::rename};
-use anyhow::{Result};
+use crate::{config::{Profile, Config}, context::AppContext, git::find_profile_key, prompt, validation::{self, email::is_email, signing_key}, commands::rename};
+use anyhow::{bail, Result};
 use dialoguer::{Confirm, Input, theme::ColorfulTheme};
+use indexmap::IndexMap;
 
-fn retrieve_profile_key_value(config: &Config) -> Result<(String, Profile)> {
-    let profile_name: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enter profile name")
-        .validate_with(|input: &String| -> Result<()> {
-            validation::profile_key::no_duplicates(input, &config.profile)?;
-            Ok(())
-        })
-        .interact_text()?;
-
-    let name: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enter user name (user.name)")
-        .interact_text()?;
-
-    let email: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enter email (user.email)")
-        .validate_with(|input: &String| is_email(input))
-        .interact_text()?;
-
-    let should_set_signingkey = Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt("Do you want to set signing key (user.signingkey)")
-        .default(false)
-        .interact()?;
-
-    let maybe_signingkey: Option<String> = if should_set_signingkey {
-        let signingkey = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Enter signing key (user.singingkey)")
+/// Values supplied on the command line. Whatever is missing is asked interactively.
+#[derive(Default)]
+pub struct NewProfileArgs {
+    pub profile_key: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub signingkey: Option<String>,
+    /// Key of the abstract profile to create the new profile from
+    pub template: Option<String>,
+    /// Values for the placeholders of the template
+    pub values: IndexMap<String, String>,
+}
+
+fn retrieve_profile_key(config: &Config, supplied_profile_key: &Option<String>) -> Result<String> {
+    if let Some(profile_key) = supplied_profile_key {
+        validation::profile_key::no_duplicates(profile_key, &config.profile)?;
+        return Ok(profile_key.to_owned());
+    }
+
+    prompt::input_profile_key(&config.profile)
+}
+
+/// Renders the given template, asking for the values of the placeholders not supplied yet
+fn render_template(config: &Config, template_key: &str, values: &IndexMap<String, String>) -> Result<Profile> {
+    if !config.has_profile_key(template_key) {
+        bail!("Template {} doesn't exist", template_key);
+    }
+    if !config.is_template(template_key) {
+        bail!("Profile {} is not a template", template_key);
+    }
+
+    let (template, _) = config.resolve_profile(template_key)?;
+
+    let mut values = values.clone();
+    for placeholder in template.placeholders() {
+        if values.contains_key(&placeholder) {
+            continue;
+        }
+        let value: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Enter {}", placeholder))
             .interact_text()?;
-        Some(signingkey)
+        values.insert(placeholder, value);
+    }
+
+    template.render(&values)
+}
+
+fn retrieve_profile(config: &Config, args: &NewProfileArgs) -> Result<Profile> {
+    let base = match args.template.as_ref() {
+        Some(template_key) => render_template(config, template_key, &args.values)?,
+        None => Profile::default(),
+    };
+
+    // Values given as flags take precedence over the ones from the template
+    let name = args.name.to_owned().unwrap_or(base.name);
+    let email = args.email.to_owned().unwrap_or(base.email);
+    let signingkey = args.signingkey.to_owned().or(base.signingkey);
+
+    // The signing key is only asked for when nothing was supplied up front
+    let is_interactive = name.is_empty() && email.is_empty() && signingkey.is_none();
+
+    let name: String = if name.is_empty() {
+        Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Enter user name (user.name)")
+            .interact_text()?
+    } else {
+        name
+    };
+
+    let email: String = if email.is_empty() {
+        Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Enter email (user.email)")
+            .validate_with(|input: &String| is_email(input))
+            .interact_text()?
+    } else {
+        is_email(&email)?;
+        email
+    };
+
+    let (maybe_signingkey, signingformat) = if is_interactive {
+        let should_set_signingkey = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Do you want to set signing key (user.signingkey)")
+            .default(false)
+            .interact()?;
+
+        if should_set_signingkey {
+            let (signingkey, signingformat) = prompt::select_signingkey(&email)?;
+            (Some(signingkey), Some(signingformat))
+        } else {
+            (None, None)
+        }
     } else {
-        None
+        let signingformat = match (args.signingkey.as_ref(), base.signingformat) {
+            // The format of the template doesn't necessarily apply to a key given as a flag
+            (None, Some(signingformat)) => Some(signingformat),
+            _ => signingkey.as_deref().map(signing_key::format_of),
+        };
+        (signingkey, signingformat)
     };
 
+    if let Some(signingkey) = maybe_signingkey.as_ref() {
+        if let Err(error) = signing_key::verify(signingkey, &email) {
+            println!("⚠️  {}", error);
+        }
+    }
+
     let new_profile = Profile {
         name,
         email,
         signingkey: maybe_signingkey,
+        signingformat,
+        ..Default::default()
     };
 
-    Ok((profile_name, new_profile))
+    Ok(new_profile)
 }
 
-pub fn execute(context: &AppContext) -> Result<()> {
+pub fn execute(context: &AppContext, args: &NewProfileArgs) -> Result<()> {
     let config = context.config_client.load()?;
 
-    let (profile_name, new_profile) = retrieve_profile_key_value(&config)?;
+    let profile_name = retrieve_profile_key(&config, &args.profile_key)?;
+    let new_profile = retrieve_profile(&config, args)?;
 
     // Since some commands such as `git profile current` depend on value matching,
     // it should not be allow to create a new profile with the same values as one of the existing ones.
-    if let Some(existing_profile_key) = find_profile_key(&config.profile, &new_profile) {
+    if let Some(existing_profile_key) = find_profile_key(&config.resolved_profiles()?, &new_profile) {
         println!(
             "We found an existing profile with the same values: {}",
             existing_profile_key
@@ -60,7 +138,7 @@ pub fn execute(context: &AppContext) -> Result<()> {
             .default(false)
             .interact()?;
         if should_rename {
-            rename::execute(&context, &existing_profile_key, &profile_name)?;
+            rename::execute(context, &existing_profile_key, &profile_name)?;
         }
         return Ok(())
     }
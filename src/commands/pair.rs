@@ -0,0 +1,128 @@
+use anyhow::{anyhow, bail, Result};
+use std::{path::Path, process::Command};
+
+use crate::{
+    commands::guard,
+    config::{Config, Person},
+    context::AppContext,
+    git::{self, Level},
+};
+
+/// Keys of the teammates of the current session, separated by spaces
+pub const PAIR: &str = "gitprofile.pair";
+/// Hook adding the trailers of the session
+pub const TRAILERS_HOOK_NAME: &str = "prepare-commit-msg";
+
+const LOCAL: Option<Level> = Some(Level::Local);
+
+/// `--trailer` options of `git interpret-trailers`, one per teammate
+fn trailer_args(teammates: &[&Person]) -> Vec<String> {
+    teammates
+        .iter()
+        .flat_map(|teammate| {
+            ["--trailer".to_owned(), format!("Co-authored-by: {} <{}>", teammate.name, teammate.email)]
+        })
+        .collect()
+}
+
+fn teammates_of<'a>(config: &'a Config, teammate_keys: &[String]) -> Result<Vec<&'a Person>> {
+    teammate_keys
+        .iter()
+        .map(|key| config.teammate.get(key).ok_or_else(|| anyhow!("Teammate {} doesn't exist", key)))
+        .collect()
+}
+
+/// Names of the teammates of the current session, if any
+pub fn current(context: &AppContext, config: &Config) -> Result<Option<Vec<String>>> {
+    let Some(keys) = context.git_config_client.get_value(PAIR, &None)? else {
+        return Ok(None);
+    };
+    let names = keys
+        .split_whitespace()
+        .map(|key| config.teammate.get(key).map(|teammate| teammate.name.to_owned()).unwrap_or(key.to_owned()))
+        .collect();
+    Ok(Some(names))
+}
+
+/// Whether a session was started in the current repository
+pub fn is_pairing(context: &AppContext) -> Result<bool> {
+    Ok(context.git_config_client.get_value(PAIR, &LOCAL)?.is_some())
+}
+
+/// Adds the trailers of the current session to the message of the commit being made, from the
+/// prepare-commit-msg hook. Trailers already in the message, as when amending, aren't repeated.
+pub fn add_trailers(context: &AppContext, message_path: &Path) -> Result<()> {
+    let Some(keys) = context.git_config_client.get_value(PAIR, &None)? else {
+        return Ok(());
+    };
+    let config = context.config_client.load()?;
+    let teammate_keys: Vec<String> = keys.split_whitespace().map(str::to_owned).collect();
+    let teammates = teammates_of(&config, &teammate_keys)?;
+
+    let status = Command::new("git")
+        .args(["interpret-trailers", "--in-place", "--if-exists", "addIfDifferent"])
+        .args(trailer_args(&teammates))
+        .arg(message_path)
+        .status()?;
+    if !status.success() {
+        bail!("Couldn't add the Co-authored-by trailers");
+    }
+    Ok(())
+}
+
+pub fn start(context: &AppContext, teammate_keys: &[String]) -> Result<()> {
+    let config = context.config_client.load()?;
+    let teammates = teammates_of(&config, teammate_keys)?;
+    if git::git_common_dir()?.is_none() {
+        bail!("Not in a git repository");
+    }
+
+    // The trailers are added by the prepare-commit-msg hook, which also covers `git commit -m`
+    guard::install_hooks(context, false)?;
+    context.git_config_client.set_value(PAIR, &teammate_keys.join(" "), &LOCAL)?;
+
+    let names: Vec<&str> = teammates.iter().map(|teammate| teammate.name.as_str()).collect();
+    println!("✨ Pairing with {}", names.join(", "));
+    Ok(())
+}
+
+pub fn clear(context: &AppContext) -> Result<()> {
+    if !is_pairing(context)? {
+        bail!("Not pairing in this repository");
+    }
+    context.git_config_client.unset(PAIR, &LOCAL)?;
+
+    // The guard still needs the hooks
+    if !guard::is_installed(context, false)? {
+        guard::uninstall_hooks(context, false)?;
+    }
+
+    println!("✨ Ended the pairing session");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_trailer_args() {
+        let alice = Person {
+            name: "Alice".to_string(),
+            email: "alice@work.com".to_string(),
+        };
+        let bob = Person {
+            name: "Bob".to_string(),
+            email: "bob@work.com".to_string(),
+        };
+        assert_eq!(
+            trailer_args(&[&alice, &bob]),
+            vec![
+                "--trailer",
+                "Co-authored-by: Alice <alice@work.com>",
+                "--trailer",
+                "Co-authored-by: Bob <bob@work.com>"
+            ]
+        );
+    }
+}
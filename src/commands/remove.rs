@@ -5,12 +5,17 @@ use crate::context::AppContext;
 pub fn execute(context: &AppContext, profile_key: &str) -> Result<()> {
     let config = context.config_client.load()?;
 
-    if !config.has_profile_key(&profile_key) {
+    if !config.has_profile_key(profile_key) {
         bail!("Profile {} doesn't exist", &profile_key);
     }
 
+    let children = config.children_of(profile_key);
+    if !children.is_empty() {
+        bail!("Profile {} is extended by {}", &profile_key, children.join(", "));
+    }
+
     let mut config = config;
-    config.remove_profile(&profile_key);
+    config.remove_profile(profile_key);
     let config = config;
 
     context.config_client.save(&config)?;
@@ -0,0 +1,264 @@
+use anyhow::{anyhow, Result};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::{
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use crate::{
+    config::{Config, Profile},
+    context::AppContext,
+    discovery,
+    git::{self, find_profile_key},
+    output::OutputFormat,
+    prompt, rules,
+};
+
+#[derive(Debug, Serialize)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+    pub signingkey: Option<String>,
+    /// Key of the matching profile, if any
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepositoryReport {
+    pub path: String,
+    /// Identity found at each level
+    pub levels: IndexMap<&'static str, Identity>,
+    /// Level the identity in use comes from
+    pub effective_level: Option<&'static str>,
+    /// Profile expected by the first matching rule
+    pub expected: Option<String>,
+    /// Whether the identity in use isn't the one of the expected profile
+    pub drift: bool,
+    /// Why the repository couldn't be inspected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RepositoryReport {
+    fn failed(path: &Path, error: anyhow::Error) -> Self {
+        RepositoryReport {
+            path: path.display().to_string(),
+            levels: IndexMap::new(),
+            effective_level: None,
+            expected: None,
+            drift: false,
+            error: Some(error.to_string()),
+        }
+    }
+
+    pub fn effective(&self) -> Option<&Identity> {
+        self.effective_level.map(|level| &self.levels[level])
+    }
+}
+
+/// Reports on the repository the git client of the context runs in
+fn inspect(context: &AppContext, config: &Config, profiles: &IndexMap<String, Profile>, path: &Path) -> Result<RepositoryReport> {
+    let git_config_client = context.git_config_client.as_ref();
+    // git fails on a broken repository, e.g. with an invalid config, which would read as no identity
+    let git_dir = git_config_client.git_dir()?.ok_or_else(|| anyhow!("git can't read this repository"))?;
+
+    let mut levels = IndexMap::new();
+    for (label, level) in git::identity_levels(git_config_client)? {
+        if let Some(profile) = git_config_client.get(&Some(level))? {
+            let profile_key = find_profile_key(profiles, &profile);
+            levels.insert(
+                label,
+                Identity {
+                    name: profile.name,
+                    email: profile.email,
+                    signingkey: profile.signingkey,
+                    profile: profile_key,
+                },
+            );
+        }
+    }
+    let effective_level = levels.keys().last().copied();
+
+    let expected = rules::rule_for(config, &git_dir).map(|rule| rule.profile.to_owned());
+    let effective_profile = effective_level.and_then(|level| levels[level].profile.as_ref());
+    let drift = expected.is_some() && effective_profile != expected.as_ref();
+
+    Ok(RepositoryReport {
+        path: path.display().to_string(),
+        levels,
+        effective_level,
+        expected,
+        drift,
+        error: None,
+    })
+}
+
+fn print_table(reports: &[RepositoryReport]) {
+    let rows: Vec<[String; 5]> = reports
+        .iter()
+        .map(|report| {
+            let (level, identity, profile) = match (report.effective(), report.error.as_ref()) {
+                (_, Some(error)) => ("-".to_owned(), format!("error: {}", error), "-".to_owned()),
+                (Some(identity), None) => (
+                    report.effective_level.unwrap_or("-").to_owned(),
+                    format!("{} <{}>", identity.name, identity.email),
+                    identity.profile.to_owned().unwrap_or("(unknown)".to_owned()),
+                ),
+                (None, None) => ("-".to_owned(), "-".to_owned(), "-".to_owned()),
+            };
+            let expected = match (report.expected.as_ref(), report.drift) {
+                (Some(expected), true) => format!("{} (drift)", expected),
+                (Some(expected), false) => expected.to_owned(),
+                (None, _) => "-".to_owned(),
+            };
+            [report.path.to_owned(), level, identity, profile, expected]
+        })
+        .collect();
+
+    let header = ["REPOSITORY", "LEVEL", "IDENTITY", "PROFILE", "EXPECTED"].map(str::to_owned);
+    let mut widths = header.clone().map(|column| column.len());
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+/// Groups the identities that don't match a profile, each with where it was found: the level for
+/// the system and global ones, which every repository shares, or else the repository
+fn unknown_identities(reports: &[RepositoryReport]) -> IndexMap<(&str, &str, Option<&str>), Vec<&str>> {
+    let mut identities: IndexMap<(&str, &str, Option<&str>), Vec<&str>> = IndexMap::new();
+    for report in reports {
+        for (level, identity) in report.levels.iter() {
+            if identity.profile.is_some() {
+                continue;
+            }
+            let origin = match *level {
+                "system" | "global" => *level,
+                _ => report.path.as_str(),
+            };
+            let key = (identity.name.as_str(), identity.email.as_str(), identity.signingkey.as_deref());
+            let origins = identities.entry(key).or_default();
+            if !origins.contains(&origin) {
+                origins.push(origin);
+            }
+        }
+    }
+    identities
+}
+
+/// Offers to turn each identity that doesn't match a profile into a new one, once per identity
+fn offer_import(context: &AppContext, reports: &[RepositoryReport]) -> Result<()> {
+    let mut config = context.config_client.load()?;
+    let mut imported: Vec<(String, String)> = vec![];
+
+    for ((name, email, signingkey), origins) in unknown_identities(reports) {
+        let profile = Profile {
+            name: name.to_owned(),
+            email: email.to_owned(),
+            signingkey: signingkey.map(str::to_owned),
+            ..Default::default()
+        };
+        let should_import = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Import {} <{}> ({}) as a profile?", name, email, origins.join(", ")))
+            .default(false)
+            .interact()?;
+        if !should_import {
+            continue;
+        }
+        let profile_key = prompt::input_profile_key(&config.profile)?;
+        config.upsert_profile(&profile_key, profile);
+        imported.push((profile_key, email.to_owned()));
+    }
+
+    if !imported.is_empty() {
+        context.config_client.save(&config)?;
+        imported
+            .iter()
+            .for_each(|(profile_key, email)| println!("✨ Imported {} as {}", email, profile_key));
+    }
+    Ok(())
+}
+
+pub fn execute(context: &AppContext, root: &Path, format: OutputFormat) -> Result<()> {
+    let config = context.config_client.load()?;
+    let profiles = config.resolved_profiles()?;
+
+    let root = std::path::absolute(root)?;
+    let repositories = discovery::find_repositories(&root)?;
+
+    // Git runs in each repository. A repository that can't be inspected is reported without
+    // stopping the scan.
+    let mut reports = vec![];
+    for repository in repositories.iter() {
+        let report = inspect(&context.in_repository(repository), &config, &profiles, repository)
+            .unwrap_or_else(|error| RepositoryReport::failed(repository, error));
+        reports.push(report);
+    }
+
+    match format {
+        OutputFormat::Text => {
+            print_table(&reports);
+            if io::stdin().is_terminal() {
+                offer_import(context, &reports)?;
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(path: &str, levels: &[(&'static str, &str, Option<&str>)]) -> RepositoryReport {
+        let levels = levels
+            .iter()
+            .map(|(level, email, profile)| {
+                let identity = Identity {
+                    name: "Foo".to_owned(),
+                    email: email.to_string(),
+                    signingkey: None,
+                    profile: profile.map(str::to_owned),
+                };
+                (*level, identity)
+            })
+            .collect();
+        RepositoryReport {
+            path: path.to_owned(),
+            levels,
+            effective_level: None,
+            expected: None,
+            drift: false,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn lists_each_unknown_identity_once() {
+        let reports = vec![
+            report("/src/a", &[("global", "foo@home.com", None), ("local", "foo@old.com", None)]),
+            report("/src/b", &[("global", "foo@home.com", None), ("local", "foo@old.com", None)]),
+            report("/src/c", &[("global", "foo@home.com", None), ("local", "foo@work.com", Some("work"))]),
+        ];
+        let identities: Vec<(&str, Vec<&str>)> = unknown_identities(&reports)
+            .into_iter()
+            .map(|((_, email, _), origins)| (email, origins))
+            .collect();
+        assert_eq!(
+            identities,
+            vec![("foo@home.com", vec!["global"]), ("foo@old.com", vec!["/src/a", "/src/b"])]
+        );
+    }
+}
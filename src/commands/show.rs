@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 
-use crate::{config::Profile, context::AppContext};
+use crate::{config::{FieldOrigins, Profile}, context::AppContext};
 
 pub enum ProfileProperty {
     Name,
@@ -10,23 +10,82 @@ pub enum ProfileProperty {
     Profile,
 }
 
-fn print_profile(profile: &Profile) -> String {
-    let mut lines = vec![
-        format!("user.name={}", profile.name),
-        format!("user.email={}", profile.email),
-    ];
+pub fn print_profile(profile: &Profile) -> String {
+    let mut lines = vec![];
+    if let Some(extends) = profile.extends.as_ref() {
+        lines.push(format!("profile.extends={}", extends));
+    }
+    if let Some(person) = profile.person.as_ref() {
+        lines.push(format!("profile.person={}", person));
+    }
+    if !profile.name.is_empty() {
+        lines.push(format!("user.name={}", profile.name));
+    }
+    if !profile.email.is_empty() {
+        lines.push(format!("user.email={}", profile.email));
+    }
     if let Some(signingkey) = profile.signingkey.as_ref() {
         lines.push(format!("user.signingkey={}", signingkey));
     }
+    if let Some(signingformat) = profile.signingformat.as_ref() {
+        lines.push(format!("gpg.format={}", signingformat.as_str()));
+    }
+    if let Some(ssh_command) = profile.ssh_command() {
+        lines.push(format!("core.sshCommand={}", ssh_command));
+    }
+    for rewrite in profile.url.iter() {
+        lines.push(format!("url.{}.insteadOf={}", rewrite.base, rewrite.insteadof));
+    }
+    for credential in profile.credential.iter() {
+        lines.push(format!("credential.{}.username={}", credential.url, credential.username));
+        if let Some(helper) = credential.helper.as_ref() {
+            lines.push(format!("credential.{}.helper={}", credential.url, helper));
+        }
+    }
+    let lines = lines;
+    lines.join("\n")
+}
+
+fn print_resolved_profile(profile: &Profile, origins: &FieldOrigins) -> String {
+    let mut lines = vec![];
+    if let Some(person) = profile.person.as_ref() {
+        lines.push(format!("profile.person={} (from {})", person, origins["person"]));
+    }
+    lines.push(format!("user.name={} (from {})", profile.name, origins["name"]));
+    lines.push(format!("user.email={} (from {})", profile.email, origins["email"]));
+    if let Some(signingkey) = profile.signingkey.as_ref() {
+        lines.push(format!("user.signingkey={} (from {})", signingkey, origins["signingkey"]));
+    }
+    if let Some(signingformat) = profile.signingformat.as_ref() {
+        lines.push(format!("gpg.format={} (from {})", signingformat.as_str(), origins["signingformat"]));
+    }
+    if let Some(ssh_command) = profile.ssh_command() {
+        lines.push(format!("core.sshCommand={} (from {})", ssh_command, origins["ssh_identity"]));
+    }
+    for rewrite in profile.url.iter() {
+        lines.push(format!("url.{}.insteadOf={} (from {})", rewrite.base, rewrite.insteadof, origins["url"]));
+    }
+    for credential in profile.credential.iter() {
+        let origin = &origins["credential"];
+        lines.push(format!("credential.{}.username={} (from {})", credential.url, credential.username, origin));
+        if let Some(helper) = credential.helper.as_ref() {
+            lines.push(format!("credential.{}.helper={} (from {})", credential.url, helper, origin));
+        }
+    }
     let lines = lines;
     lines.join("\n")
 }
 
-pub fn execute(context: &AppContext, profile_key: &str) -> Result<()> {
+pub fn execute(context: &AppContext, profile_key: &str, resolved: bool) -> Result<()> {
     let config = context.config_client.load()?;
 
     if let Some(profile) = config.profile.get(profile_key) {
-        println!("{}", print_profile(profile));
+        if resolved {
+            let (resolved_profile, origins) = config.resolve_profile(profile_key)?;
+            println!("{}", print_resolved_profile(&resolved_profile, &origins));
+        } else {
+            println!("{}", print_profile(profile));
+        }
         Ok(())
     } else {
         Err(anyhow!("Profile {} doesn't exist.", profile_key))
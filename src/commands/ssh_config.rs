@@ -0,0 +1,22 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::{context::AppContext, ssh_config};
+
+pub fn execute(context: &AppContext, write: bool, maybe_file: Option<PathBuf>) -> Result<()> {
+    let config = context.config_client.load()?;
+
+    if !write {
+        let (block, warnings) = ssh_config::render(&config)?;
+        warnings.iter().for_each(|warning| eprintln!("⚠️  {}", warning));
+        println!("{}", block);
+        return Ok(());
+    }
+
+    let path = maybe_file.unwrap_or_else(ssh_config::default_path);
+    let (host_count, warnings) = ssh_config::write(&config, &path)?;
+    warnings.iter().for_each(|warning| println!("⚠️  {}", warning));
+
+    println!("✨ Wrote {} host(s) to {}", host_count, path.display());
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+
+use crate::{
+    commands::{pair, strict},
+    config::Config,
+    context::AppContext,
+    git,
+    rules,
+};
+
+/// Describes which profile each level holds, `None` for a level without identity, the last entry
+/// being the effective profile
+pub fn level_identities(context: &AppContext, config: &Config) -> Result<Vec<(&'static str, Option<String>)>> {
+    let git_config_client = context.git_config_client.as_ref();
+
+    let mut identities = vec![];
+    for (label, level) in git::identity_levels(git_config_client)? {
+        let level = Some(level);
+        let description = match git_config_client.get(&level)? {
+            Some(identity) => match git_config_client.get_profile_key(config, &level)? {
+                Some(profile_key) => Some(profile_key),
+                None => Some(format!("(unmanaged) {} <{}>", identity.name, identity.email)),
+            },
+            None => None,
+        };
+        identities.push((label, description));
+    }
+
+    let effective_key = git_config_client.get_profile_key(config, &None)?;
+    identities.push(("current", effective_key));
+    Ok(identities)
+}
+
+/// The profile in use, from the last entry of `level_identities`
+pub fn current_profile_key<'a>(level_identities: &'a [(&'static str, Option<String>)]) -> Option<&'a str> {
+    level_identities
+        .last()
+        .filter(|(label, _)| *label == "current")
+        .and_then(|(_, profile_key)| profile_key.as_deref())
+}
+
+/// A line of `level_identities`, as displayed
+pub fn format_level_identity(label: &str, description: &Option<String>) -> String {
+    format!("{:<9}{}", label, description.as_deref().unwrap_or("-"))
+}
+
+fn generate_output(context: &AppContext) -> Result<Vec<String>> {
+    let git_config_client = context.git_config_client.as_ref();
+    let config = context.config_client.load()?;
+    let profiles = config.resolved_profiles()?;
+
+    let level_identities = level_identities(context, &config)?;
+    let effective_key = current_profile_key(&level_identities);
+    let mut lines: Vec<String> = level_identities
+        .iter()
+        .map(|(label, description)| format_level_identity(label, description))
+        .collect();
+
+    if let Some(rule) = git::git_dir()?.and_then(|git_dir| rules::rule_for(&config, &git_dir).cloned()) {
+        lines.push(format!("{:<9}{} (rule {})", "expected", rule.profile, rule.gitdir));
+    }
+
+    if let Some(profile) = effective_key.and_then(|key| profiles.get(key)) {
+        if let Some(ssh_identity) = profile.ssh_identity.as_ref() {
+            lines.push(format!("ssh identity: {}", ssh_identity));
+        }
+    }
+    if let Some(ssh_command) = git_config_client.get_value("core.sshCommand", &None)? {
+        lines.push(format!("core.sshCommand={}", ssh_command));
+    }
+
+    if let Some(names) = pair::current(context, &config)? {
+        lines.push(format!("pairing with: {}", names.join(", ")));
+    }
+
+    let strict_mode = if strict::is_enabled(context)? { "on" } else { "off" };
+    lines.push(format!("strict mode: {}", strict_mode));
+
+    Ok(lines)
+}
+
+pub fn execute(context: &AppContext) -> Result<()> {
+    generate_output(context)?
+        .iter()
+        .for_each(|line| println!("{}", line));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_current_profile_key() {
+        let mut level_identities = vec![("global", Some("work".to_owned())), ("current", Some("work".to_owned()))];
+        assert_eq!(current_profile_key(&level_identities), Some("work"));
+
+        level_identities[1].1 = None;
+        assert_eq!(current_profile_key(&level_identities), None);
+        assert_eq!(format_level_identity("current", &None), "current  -");
+    }
+}
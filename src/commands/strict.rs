@@ -0,0 +1,61 @@
+use anyhow::Result;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+use crate::{
+    commands::{apply, import},
+    context::AppContext,
+    git::Level,
+};
+
+pub const USE_CONFIG_ONLY: &str = "user.useConfigOnly";
+/// The profile that held the global identity when strict mode was enabled, restored by `strict disable`
+const STRICT_PROFILE: &str = "gitprofile.strictProfile";
+
+const GLOBAL: Option<Level> = Some(Level::Global);
+
+/// Whether git refuses to guess an identity, as set up by `strict enable`
+pub fn is_enabled(context: &AppContext) -> Result<bool> {
+    let value = context.git_config_client.get_value(USE_CONFIG_ONLY, &None)?;
+    Ok(value.is_some_and(|value| value == "true"))
+}
+
+pub fn enable(context: &AppContext) -> Result<()> {
+    let git_config_client = context.git_config_client.as_ref();
+
+    if git_config_client.get(&GLOBAL)?.is_some() {
+        let should_move = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Move the global identity into a profile, so that every repository needs its own?")
+            .default(true)
+            .interact()?;
+        if should_move {
+            let config = context.config_client.load()?;
+            if git_config_client.get_profile_key(&config, &GLOBAL)?.is_none() {
+                import::execute(context, &GLOBAL)?;
+            }
+            let config = context.config_client.load()?;
+            if let Some(profile_key) = git_config_client.get_profile_key(&config, &GLOBAL)? {
+                git_config_client.set_value(STRICT_PROFILE, &profile_key, &GLOBAL)?;
+            }
+            for key in ["user.name", "user.email", "user.signingkey"] {
+                git_config_client.unset(key, &GLOBAL)?;
+            }
+        }
+    }
+    git_config_client.set_value(USE_CONFIG_ONLY, "true", &GLOBAL)?;
+
+    println!("✨ Strict mode is on. Run `git profile apply --local <profile>` in each repository");
+    Ok(())
+}
+
+pub fn disable(context: &AppContext) -> Result<()> {
+    let git_config_client = context.git_config_client.as_ref();
+
+    git_config_client.unset(USE_CONFIG_ONLY, &GLOBAL)?;
+    if let Some(profile_key) = git_config_client.get_value(STRICT_PROFILE, &GLOBAL)? {
+        apply::execute(context, Some(&profile_key), &GLOBAL, false)?;
+        git_config_client.unset(STRICT_PROFILE, &GLOBAL)?;
+    }
+
+    println!("✨ Strict mode is off");
+    Ok(())
+}
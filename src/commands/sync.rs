@@ -0,0 +1,14 @@
+use anyhow::Result;
+
+use crate::{allowed_signers, context::AppContext};
+
+pub fn execute(context: &AppContext) -> Result<()> {
+    let config = context.config_client.load()?;
+    let path = allowed_signers::path_for(context.config_client.path());
+
+    let (signer_count, warnings) = allowed_signers::write(&config, &path)?;
+    warnings.iter().for_each(|warning| println!("⚠️  {}", warning));
+
+    println!("✨ Wrote {} signer(s) to {}", signer_count, path.display());
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anyhow::{bail, Result};
+use dialoguer::{theme::ColorfulTheme, Input};
+
+use crate::{
+    config::{Config, Person},
+    context::AppContext,
+    validation::email::is_email,
+};
+
+/// Keys are stored separated by spaces in gitprofile.pair
+fn validate_key(config: &Config, key: &str) -> Result<()> {
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        bail!("{} can't be used as a teammate key", key);
+    }
+    if config.teammate.contains_key(key) {
+        bail!("Teammate {} already exists", key);
+    }
+    Ok(())
+}
+
+/// Adds a teammate to the roster, asking for the name and email not supplied
+pub fn add(context: &AppContext, key: &str, maybe_name: Option<&str>, maybe_email: Option<&str>) -> Result<()> {
+    let mut config = context.config_client.load()?;
+    validate_key(&config, key)?;
+
+    let name: String = match maybe_name {
+        Some(name) => name.to_owned(),
+        None => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Enter name")
+            .interact_text()?,
+    };
+    let email: String = match maybe_email {
+        Some(email) => email.to_owned(),
+        None => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Enter email")
+            .validate_with(|input: &String| is_email(input))
+            .interact_text()?,
+    };
+    let teammate = Person { name, email };
+    teammate.validate()?;
+
+    config.teammate.insert(key.to_owned(), teammate);
+    context.config_client.save(&config)?;
+    println!("✨ Added {} to the teammates", key);
+    Ok(())
+}
+
+pub fn list(context: &AppContext) -> Result<()> {
+    let config = context.config_client.load()?;
+    if config.teammate.is_empty() {
+        bail!("No teammate has been added. Run `git profile teammate add <key>`");
+    }
+
+    let width = config.teammate.keys().map(String::len).max().unwrap_or(0);
+    for (key, teammate) in config.teammate.iter() {
+        println!("{:<width$}  {} <{}>", key, teammate.name, teammate.email, width = width);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_keys() {
+        let config: Config = toml::from_str(
+            r#"
+            [teammate.alice]
+            name = 'Alice'
+            email = 'alice@work.com'
+        "#,
+        )
+        .unwrap();
+        assert!(validate_key(&config, "bob").is_ok());
+        assert!(validate_key(&config, "alice").is_err());
+        assert!(validate_key(&config, "bob roe").is_err());
+        assert!(validate_key(&config, "").is_err());
+    }
+}
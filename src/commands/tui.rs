@@ -0,0 +1,357 @@
+use std::io;
+
+use anyhow::{bail, Result};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use ratatui::{
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEventKind},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    commands::{apply, edit, new, remove, rename, show, status},
+    config::Config,
+    context::AppContext,
+    prompt,
+};
+
+const SHORTCUTS: &str = "a apply  n new  e edit  d delete  r rename  q quit";
+
+enum Action {
+    Apply,
+    New,
+    Edit,
+    Delete,
+    Rename,
+}
+
+/// What the dashboard displays, rebuilt from the config file and git config after each action
+pub struct App {
+    profile_keys: Vec<String>,
+    descriptions: Vec<String>,
+    details: Vec<String>,
+    level_identities: Vec<(&'static str, Option<String>)>,
+    selected: usize,
+    status: Option<String>,
+}
+
+impl App {
+    pub fn new(
+        config: &Config,
+        level_identities: Vec<(&'static str, Option<String>)>,
+        current_profile_key: Option<&str>,
+    ) -> Result<Self> {
+        let profile_keys: Vec<String> = config.profile.keys().cloned().collect();
+        let descriptions = prompt::describe_profiles(config, &profile_keys, current_profile_key)?;
+        // Resolved like the descriptions, so that inherited fields show up in both panes
+        let details = profile_keys
+            .iter()
+            .map(|profile_key| Ok(show::print_profile(&config.resolve_profile(profile_key)?.0)))
+            .collect::<Result<_>>()?;
+        let selected = current_profile_key
+            .and_then(|current| profile_keys.iter().position(|profile_key| profile_key == current))
+            .unwrap_or(0);
+
+        Ok(App {
+            profile_keys,
+            descriptions,
+            details,
+            level_identities,
+            selected,
+            status: None,
+        })
+    }
+
+    fn load(context: &AppContext) -> Result<Self> {
+        let config = context.config_client.load()?;
+        let level_identities = status::level_identities(context, &config)?;
+        let current_profile_key = status::current_profile_key(&level_identities).map(str::to_owned);
+        App::new(&config, level_identities, current_profile_key.as_deref())
+    }
+
+    /// Reloads the data after an action, staying on the same profile when it still exists
+    fn reload(&mut self, context: &AppContext) -> Result<()> {
+        let selected_key = self.selected_profile_key().map(str::to_owned);
+        let status = self.status.take();
+
+        *self = App::load(context)?;
+        if let Some(index) = selected_key.and_then(|key| self.profile_keys.iter().position(|profile_key| *profile_key == key)) {
+            self.selected = index;
+        }
+        self.status = status;
+        Ok(())
+    }
+
+    fn selected_profile_key(&self) -> Option<&str> {
+        self.profile_keys.get(self.selected).map(String::as_str)
+    }
+
+    fn select_next(&mut self) {
+        if self.selected + 1 < self.profile_keys.len() {
+            self.selected += 1;
+        }
+    }
+
+    fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+}
+
+pub fn render(frame: &mut Frame, app: &App) {
+    let [main_area, levels_area, footer_area] = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(app.level_identities.len() as u16 + 2),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [profiles_area, details_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main_area);
+
+    let profiles = List::new(app.descriptions.iter().map(String::as_str))
+        .block(Block::bordered().title(" Profiles "))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(app.selected_profile_key().map(|_| app.selected));
+    frame.render_stateful_widget(profiles, profiles_area, &mut list_state);
+
+    let details_title = format!(" {} ", app.selected_profile_key().unwrap_or("No profile"));
+    let details = app.details.get(app.selected).map(String::as_str).unwrap_or("");
+    frame.render_widget(
+        Paragraph::new(details).block(Block::bordered().title(details_title)),
+        details_area,
+    );
+
+    let levels: Vec<Line> = app
+        .level_identities
+        .iter()
+        .map(|(label, description)| Line::from(status::format_level_identity(label, description)))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(levels).block(Block::bordered().title(" Current identity ")),
+        levels_area,
+    );
+
+    let footer = match app.status.as_ref() {
+        Some(status) => Line::from(vec![SHORTCUTS.into(), "  ".into(), status.as_str().bold()]),
+        None => Line::from(SHORTCUTS),
+    };
+    frame.render_widget(footer, footer_area);
+}
+
+/// Runs the action through the same functions as the CLI, and reports what was done
+fn perform(context: &AppContext, action: &Action, maybe_profile_key: Option<&str>) -> Result<String> {
+    match (action, maybe_profile_key) {
+        (Action::New, _) => {
+            new::execute(context, &new::NewProfileArgs::default())?;
+            Ok("Created a new profile".to_owned())
+        }
+        (_, None) => bail!("No profile selected"),
+        (Action::Apply, Some(profile_key)) => {
+            let level = prompt::select_level(context.git_config_client.as_ref())?;
+            apply::execute(context, Some(profile_key), &Some(level), false)?;
+            Ok(format!("Applied {}", profile_key))
+        }
+        (Action::Edit, Some(profile_key)) => {
+            edit::execute(context, &Some(profile_key.to_owned()))?;
+            Ok(format!("Edited {}", profile_key))
+        }
+        (Action::Delete, Some(profile_key)) => {
+            let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("Remove {}?", profile_key))
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                return Ok(format!("Kept {}", profile_key));
+            }
+            remove::execute(context, profile_key)?;
+            Ok(format!("Removed {}", profile_key))
+        }
+        (Action::Rename, Some(profile_key)) => {
+            let config = context.config_client.load()?;
+            let new_profile_key = prompt::input_profile_key(&config.profile)?;
+            rename::execute(context, profile_key, &new_profile_key)?;
+            Ok(format!("Renamed {} to {}", profile_key, new_profile_key))
+        }
+    }
+}
+
+/// Hands the terminal back to the shell while an interactive command runs
+fn suspended<T>(terminal: &mut DefaultTerminal, run: impl FnOnce() -> T) -> Result<T> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    let output = run();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    Ok(output)
+}
+
+fn run(terminal: &mut DefaultTerminal, context: &AppContext, app: &mut App) -> Result<()> {
+    loop {
+        terminal.draw(|frame| render(frame, app))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let action = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.select_next();
+                continue;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.select_previous();
+                continue;
+            }
+            KeyCode::Char('a') | KeyCode::Enter => Action::Apply,
+            KeyCode::Char('n') => Action::New,
+            KeyCode::Char('e') => Action::Edit,
+            KeyCode::Char('d') => Action::Delete,
+            KeyCode::Char('r') => Action::Rename,
+            _ => continue,
+        };
+
+        let profile_key = app.selected_profile_key().map(str::to_owned);
+        let outcome = suspended(terminal, || perform(context, &action, profile_key.as_deref()))?;
+        app.status = Some(match outcome {
+            Ok(report) => format!("✨ {}", report),
+            Err(error) => format!("⚠️  {}", error),
+        });
+        // An action can leave the config file broken, which the next action can fix
+        if let Err(error) = app.reload(context) {
+            app.status = Some(format!("⚠️  {}", error));
+        }
+    }
+}
+
+pub fn execute(context: &AppContext) -> Result<()> {
+    let mut app = App::load(context)?;
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, context, &mut app);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, text::Span, Terminal};
+
+    use super::*;
+
+    fn app() -> App {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.work]
+            name = 'Foo Bar'
+            email = 'foo@work.com'
+            signingkey = '~/.ssh/work.pub'
+            signingformat = 'ssh'
+
+            [profile.oss]
+            name = 'Foo'
+            email = 'foo@oss.org'
+        "#,
+        )
+        .unwrap();
+        let level_identities = vec![
+            ("system", None),
+            ("global", Some("oss".to_owned())),
+            ("local", Some("work".to_owned())),
+            ("current", Some("work".to_owned())),
+        ];
+        App::new(&config, level_identities, Some("work")).unwrap()
+    }
+
+    fn rendered(app: &App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(100, 14)).unwrap();
+        terminal.draw(|frame| render(frame, app)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                let mut line = String::new();
+                let mut x = 0;
+                while x < buffer.area.width {
+                    let symbol = buffer[(x, y)].symbol();
+                    line.push_str(symbol);
+                    // Wide symbols cover the next cell as well
+                    x += Span::raw(symbol).width().max(1) as u16;
+                }
+                line.trim_end().to_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn renders_profiles_details_and_levels() {
+        assert_eq!(
+            rendered(&app()),
+            vec![
+                "┌ Profiles ────────────────────────────────────────────────┐┌ work ────────────────────────────────┐",
+                "│  oss   Foo      foo@oss.org   unsigned                   ││user.name=Foo Bar                     │",
+                "│> work  Foo Bar  foo@work.com  signed (ssh)  current      ││user.email=foo@work.com               │",
+                "│                                                          ││user.signingkey=~/.ssh/work.pub       │",
+                "│                                                          ││gpg.format=ssh                        │",
+                "│                                                          ││                                      │",
+                "└──────────────────────────────────────────────────────────┘└──────────────────────────────────────┘",
+                "┌ Current identity ────────────────────────────────────────────────────────────────────────────────┐",
+                "│system   -                                                                                        │",
+                "│global   oss                                                                                      │",
+                "│local    work                                                                                     │",
+                "│current  work                                                                                     │",
+                "└──────────────────────────────────────────────────────────────────────────────────────────────────┘",
+                "a apply  n new  e edit  d delete  r rename  q quit",
+            ]
+        );
+    }
+
+    #[test]
+    fn details_show_inherited_fields() {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.base]
+            name = 'Foo Bar'
+
+            [profile.work]
+            extends = 'base'
+            email = 'foo@work.com'
+        "#,
+        )
+        .unwrap();
+        let app = App::new(&config, vec![], Some("work")).unwrap();
+        assert_eq!(app.details[app.selected], "user.name=Foo Bar\nuser.email=foo@work.com");
+    }
+
+    #[test]
+    fn moves_the_selection_within_the_profiles() {
+        let mut app = app();
+        app.select_next();
+        assert_eq!(app.selected_profile_key(), Some("work"));
+
+        app.select_previous();
+        app.select_previous();
+        assert_eq!(app.selected_profile_key(), Some("oss"));
+        app.status = Some("✨ Applied oss".to_owned());
+
+        let lines = rendered(&app);
+        assert!(lines[0].contains("┐┌ oss ─"));
+        assert!(lines[1].starts_with("│> oss   Foo      foo@oss.org   unsigned"));
+        assert!(lines[2].starts_with("│  work  Foo Bar  foo@work.com  signed (ssh)  current"));
+        assert_eq!(lines[13], format!("{}  ✨ Applied oss", SHORTCUTS));
+    }
+}
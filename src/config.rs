@@ -1,37 +1,278 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use toml;
 
+use crate::{
+    paths::expand_home,
+    validation::{email::is_email, signing_key},
+};
+
 use std::fs;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-pub const DEFAULT_FILE_NAME: &'static str = "gitprofile.toml";
+pub const DEFAULT_FILE_NAME: &str = "gitprofile.toml";
 
 pub type ProfileMap = IndexMap<String, Profile>;
 
+/// Maps each field of a resolved profile to the key of the profile it was taken from
+pub type FieldOrigins = IndexMap<&'static str, String>;
+
 #[serde_as]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default = "IndexMap::new")]
     #[serde(skip_serializing_if = "IndexMap::is_empty")]
     #[serde_as(as = "IndexMap<_, _>")]
-    pub profile: ProfileMap
+    pub profile: ProfileMap,
+    /// Who the profiles belong to, see `Profile::person`
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub person: IndexMap<String, Person>,
+    /// Roster of the people to pair with, see `git profile pair`
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub teammate: IndexMap<String, Person>,
+    /// Which profile repositories are expected to use, the first matching rule wins
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rule: Vec<Rule>,
+}
+
+/// Someone other than a profile: the canonical identity behind several profiles, or a teammate
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Person {
+    pub name: String,
+    pub email: String,
+}
+
+impl Person {
+    /// Checks the values like `new` does for profiles
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            bail!("No name");
+        }
+        is_email(&self.email).map_err(|_| anyhow!("Invalid email address {}", self.email))
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+/// Expects repositories whose git directory matches `gitdir` to use `profile`.
+/// `gitdir` follows the syntax of git's `includeIf "gitdir:..."` conditions.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Rule {
+    pub gitdir: String,
+    /// Matches `gitdir` case-insensitively, like `includeIf "gitdir/i:..."`
+    #[serde(default, rename = "ignorecase", skip_serializing_if = "is_false")]
+    pub ignore_case: bool,
+    pub profile: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
 pub struct Profile {
+    /// Key of the profile to inherit unset fields from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Abstract profiles are templates: they can't be applied but can be used to create new profiles
+    #[serde(default, rename = "abstract", skip_serializing_if = "is_false")]
+    pub is_abstract: bool,
+    /// Empty when inherited
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub name: String,
+    /// Empty when inherited
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub email: String,
     pub signingkey: Option<String>,
+    /// gpg.format to use with the signing key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signingformat: Option<SigningFormat>,
+    /// SSH private key used to push and fetch, through core.sshCommand
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_identity: Option<String>,
+    /// URL rewrites (url.<base>.insteadOf) applied along with the profile
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub url: Vec<UrlRewrite>,
+    /// Key of the person this profile belongs to, used to generate .mailmap entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub person: Option<String>,
+    /// Credentials used for HTTPS remotes (credential.<url>.*) applied along with the profile
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credential: Vec<Credential>,
+}
+
+/// Makes git use `base` for URLs starting with `insteadof`,
+/// e.g. a host alias of `~/.ssh/config` for a multi-account host
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct UrlRewrite {
+    pub base: String,
+    pub insteadof: String,
+}
+
+/// Account to use for the remotes under `url`, and optionally the helper storing its password
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Credential {
+    pub url: String,
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub helper: Option<String>,
+}
+
+impl Credential {
+    /// Whether the URL is narrower than a host, e.g. `https://github.com/acme`.
+    /// git only sends the path of the remote, and thus matches such URLs, with `credential.useHttpPath`.
+    pub fn has_path(&self) -> bool {
+        let without_scheme = self.url.split_once("://").map_or(self.url.as_str(), |(_, rest)| rest);
+        without_scheme
+            .split_once('/')
+            .is_some_and(|(_, path)| !path.trim_matches('/').is_empty())
+    }
+}
+
+/// https://git-scm.com/docs/git-config#Documentation/git-config.txt-gpgformat
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    Openpgp,
+    X509,
+    Ssh,
+}
+
+impl SigningFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SigningFormat::Openpgp => "openpgp",
+            SigningFormat::X509 => "x509",
+            SigningFormat::Ssh => "ssh",
+        }
+    }
+
+    /// Reads a gpg.format value
+    pub fn parse(value: &str) -> Option<SigningFormat> {
+        [SigningFormat::Openpgp, SigningFormat::X509, SigningFormat::Ssh]
+            .into_iter()
+            .find(|format| format.as_str() == value)
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+lazy_static! {
+    /// Matches placeholders such as `{username}` in template fields
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{([A-Za-z0-9_-]+)\}").unwrap();
+}
+
+impl Profile {
+    /// The gpg.format git signs with: the explicit one, else the format of the signing key,
+    /// which profiles written before signingformat existed rely on
+    pub fn effective_signingformat(&self) -> Option<SigningFormat> {
+        self.signingformat
+            .or_else(|| self.signingkey.as_deref().map(signing_key::format_of))
+    }
+
+    /// Whether both profiles result in the same git identity.
+    /// Only the values git reports back are compared, so that profiles can be found from the git config.
+    pub fn same_identity(&self, other: &Profile) -> bool {
+        self.name == other.name && self.email == other.email && self.signingkey == other.signingkey
+    }
+
+    /// The core.sshCommand that makes git use the SSH identity of this profile
+    pub fn ssh_command(&self) -> Option<String> {
+        self.ssh_identity.as_ref().map(|ssh_identity| {
+            let path = expand_home(ssh_identity).display().to_string();
+            let path = if path.contains(char::is_whitespace) {
+                format!("'{}'", path)
+            } else {
+                path
+            };
+            format!("ssh -i {} -o IdentitiesOnly=yes", path)
+        })
+    }
+
+    /// Git config entries other than user.* and gpg.format that applying this profile sets.
+    /// They are tracked so that switching profiles only removes what git-profile added.
+    pub fn managed_entries(&self) -> Vec<(String, String)> {
+        let mut entries = vec![];
+        if let Some(ssh_command) = self.ssh_command() {
+            entries.push(("core.sshCommand".to_owned(), ssh_command));
+        }
+        for rewrite in self.url.iter() {
+            entries.push((format!("url.{}.insteadOf", rewrite.base), rewrite.insteadof.to_owned()));
+        }
+        for credential in self.credential.iter() {
+            entries.push((format!("credential.{}.username", credential.url), credential.username.to_owned()));
+            if let Some(helper) = credential.helper.as_ref() {
+                entries.push((format!("credential.{}.helper", credential.url), helper.to_owned()));
+            }
+            if credential.has_path() {
+                entries.push((format!("credential.{}.useHttpPath", credential.url), "true".to_owned()));
+            }
+        }
+        entries
+    }
+
+    /// Lists the distinct placeholders used in the fields of this profile, in order of appearance
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut placeholders: Vec<String> = vec![];
+        let fields = [
+            Some(&self.name),
+            Some(&self.email),
+            self.signingkey.as_ref(),
+            self.ssh_identity.as_ref(),
+        ];
+        fields.into_iter().flatten().for_each(|field| {
+            PLACEHOLDER.captures_iter(field).for_each(|captures| {
+                let placeholder = captures[1].to_owned();
+                if !placeholders.contains(&placeholder) {
+                    placeholders.push(placeholder);
+                }
+            });
+        });
+        placeholders
+    }
+
+    /// Creates a concrete profile by filling in the placeholders of this one
+    pub fn render(&self, values: &IndexMap<String, String>) -> Result<Profile> {
+        let render_field = |field: &str| -> Result<String> {
+            if let Some(missing) = PLACEHOLDER
+                .captures_iter(field)
+                .find(|captures| !values.contains_key(&captures[1]))
+            {
+                bail!("No value given for {}", &missing[0]);
+            }
+            Ok(PLACEHOLDER
+                .replace_all(field, |captures: &regex::Captures| values[&captures[1]].to_owned())
+                .into_owned())
+        };
+
+        Ok(Profile {
+            name: render_field(&self.name)?,
+            email: render_field(&self.email)?,
+            signingkey: self.signingkey.as_deref().map(render_field).transpose()?,
+            signingformat: self.signingformat,
+            ssh_identity: self.ssh_identity.as_deref().map(render_field).transpose()?,
+            url: self.url.clone(),
+            credential: self.credential.clone(),
+            person: self.person.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Config {
     pub fn new() -> Self {
         Config {
             profile: IndexMap::new(),
+            person: IndexMap::new(),
+            teammate: IndexMap::new(),
+            rule: vec![],
         }
     }
 
@@ -39,6 +280,20 @@ impl Config {
         self.profile.contains_key(key)
     }
 
+    /// Whether the given key refers to an abstract profile
+    pub fn is_template(&self, key: &str) -> bool {
+        self.profile.get(key).is_some_and(|profile| profile.is_abstract)
+    }
+
+    /// Lists the keys of the abstract profiles
+    pub fn templates(&self) -> Vec<String> {
+        self.profile
+            .iter()
+            .filter(|(_, profile)| profile.is_abstract)
+            .map(|(key, _)| key.to_owned())
+            .collect()
+    }
+
     /// Upserts a profile to the given key
     pub fn upsert_profile(&mut self, key: &str, value: Profile) -> Option<Profile> {
         self.profile.insert(key.to_owned(), value)
@@ -49,14 +304,153 @@ impl Config {
         self.profile.shift_remove(key)
     }
 
-    /// Renames the given profile
+    /// Renames the given profile, and updates the profiles extending it
     pub fn rename_profile(&mut self, old_key: &str, new_key: &str) -> Option<Profile> {
         if let Some(old_profile) = self.remove_profile(old_key) {
+            self.profile.values_mut().for_each(|profile| {
+                if profile.extends.as_deref() == Some(old_key) {
+                    profile.extends = Some(new_key.to_owned());
+                }
+            });
             self.upsert_profile(new_key, old_profile)
         } else {
             None
         }
     }
+
+    /// Lists the keys of the profiles directly extending the given profile
+    pub fn children_of(&self, key: &str) -> Vec<String> {
+        self.profile
+            .iter()
+            .filter(|(_, profile)| profile.extends.as_deref() == Some(key))
+            .map(|(child_key, _)| child_key.to_owned())
+            .collect()
+    }
+
+    /// Returns the given key followed by the keys of its ancestors, nearest first
+    fn lineage<'a>(&'a self, key: &'a str) -> Result<Vec<&'a str>> {
+        let mut lineage: Vec<&str> = vec![];
+        let mut current = key;
+        loop {
+            if lineage.contains(&current) {
+                lineage.push(current);
+                bail!("Profile {} has cyclic inheritance: {}", key, lineage.join(" -> "));
+            }
+            let profile = match self.profile.get(current) {
+                Some(profile) => profile,
+                None if lineage.is_empty() => bail!("Profile {} doesn't exist", key),
+                None => bail!(
+                    "Profile {} extends {}, which doesn't exist",
+                    lineage.last().unwrap(),
+                    current
+                ),
+            };
+            lineage.push(current);
+            match profile.extends.as_deref() {
+                Some(parent) => current = parent,
+                None => return Ok(lineage),
+            }
+        }
+    }
+
+    /// Computes the effective profile by merging the given profile over its ancestors
+    pub fn resolve_profile(&self, key: &str) -> Result<(Profile, FieldOrigins)> {
+        let mut resolved = Profile::default();
+        let mut origins = FieldOrigins::new();
+
+        // Walk from the root so that descendants override their ancestors
+        for profile_key in self.lineage(key)?.into_iter().rev() {
+            let profile = &self.profile[profile_key];
+            if !profile.name.is_empty() {
+                resolved.name = profile.name.to_owned();
+                origins.insert("name", profile_key.to_owned());
+            }
+            if !profile.email.is_empty() {
+                resolved.email = profile.email.to_owned();
+                origins.insert("email", profile_key.to_owned());
+            }
+            if profile.signingkey.is_some() {
+                resolved.signingkey = profile.signingkey.to_owned();
+                origins.insert("signingkey", profile_key.to_owned());
+            }
+            if profile.signingformat.is_some() {
+                resolved.signingformat = profile.signingformat;
+                origins.insert("signingformat", profile_key.to_owned());
+            }
+            if profile.ssh_identity.is_some() {
+                resolved.ssh_identity = profile.ssh_identity.to_owned();
+                origins.insert("ssh_identity", profile_key.to_owned());
+            }
+            // Rewrites are inherited as a whole
+            if !profile.url.is_empty() {
+                resolved.url = profile.url.to_owned();
+                origins.insert("url", profile_key.to_owned());
+            }
+            if profile.person.is_some() {
+                resolved.person = profile.person.to_owned();
+                origins.insert("person", profile_key.to_owned());
+            }
+            if !profile.credential.is_empty() {
+                resolved.credential = profile.credential.to_owned();
+                origins.insert("credential", profile_key.to_owned());
+            }
+        }
+
+        // Being abstract is not inherited
+        resolved.is_abstract = self.profile[key].is_abstract;
+
+        Ok((resolved, origins))
+    }
+
+    /// Resolves every concrete profile, keeping the order of the config file
+    pub fn resolved_profiles(&self) -> Result<ProfileMap> {
+        self.profile
+            .iter()
+            .filter(|(_, profile)| !profile.is_abstract)
+            .map(|(key, _)| Ok((key.to_owned(), self.resolve_profile(key)?.0)))
+            .collect()
+    }
+
+    /// Checks that inheritance is well-formed and that every concrete profile ends up complete
+    pub fn validate(&self) -> Result<()> {
+        for (key, raw_profile) in self.profile.iter() {
+            let (profile, _) = self.resolve_profile(key)?;
+            if raw_profile.is_abstract {
+                continue;
+            }
+            if let Some(placeholder) = profile.placeholders().first() {
+                bail!("Profile {} has an unfilled placeholder {{{}}}", key, placeholder);
+            }
+            if profile.name.is_empty() {
+                bail!("Profile {} has no name (user.name)", key);
+            }
+            if profile.email.is_empty() {
+                bail!("Profile {} has no email (user.email)", key);
+            }
+        }
+        for (key, person) in self.person.iter() {
+            person.validate().with_context(|| format!("Invalid person {}", key))?;
+        }
+        for (key, teammate) in self.teammate.iter() {
+            teammate.validate().with_context(|| format!("Invalid teammate {}", key))?;
+        }
+        for (key, raw_profile) in self.profile.iter() {
+            if let Some(person) = raw_profile.person.as_ref() {
+                if !self.person.contains_key(person) {
+                    bail!("Profile {} belongs to person {}, which doesn't exist", key, person);
+                }
+            }
+        }
+        for rule in self.rule.iter() {
+            if !self.has_profile_key(&rule.profile) {
+                bail!("Rule {} uses profile {}, which doesn't exist", rule.gitdir, rule.profile);
+            }
+            if self.is_template(&rule.profile) {
+                bail!("Rule {} uses profile {}, which is a template", rule.gitdir, rule.profile);
+            }
+        }
+        Ok(())
+    }
 }
 
 pub struct AppConfigClient {
@@ -70,11 +464,16 @@ impl AppConfigClient {
 }
 
 pub trait Persist {
+    fn path(&self) -> &Path;
     fn load(&self) -> Result<Config>;
     fn save(&self, config: &Config) -> Result<()>;
 }
 
 impl Persist for AppConfigClient {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
     fn load(&self) -> Result<Config> {
         // Create the containing dir if not exists
         let containing_dir = match self.path.parent() {
@@ -86,6 +485,7 @@ impl Persist for AppConfigClient {
         // Create the file if not exists
         fs::OpenOptions::new()
             .create(true)
+            .truncate(false)
             .write(true)
             .open(&self.path)
             .with_context(|| format!("Can't open {}", &self.path.as_path().display()))?;
@@ -100,6 +500,9 @@ impl Persist for AppConfigClient {
         file.read_to_string(&mut content)?;
 
         let config: Config = toml::from_str(&content)?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid config in {}", &self.path.display()))?;
         Ok(config)
     }
 
@@ -158,6 +561,9 @@ mod serialize {
     fn empty() {
         let config = Config {
             profile: IndexMap::new(),
+            person: IndexMap::new(),
+            teammate: IndexMap::new(),
+            rule: vec![],
         };
         let toml_str = toml::to_string(&config).unwrap();
         assert_eq!(toml_str, "");
@@ -170,14 +576,19 @@ mod serialize {
                 "default".to_string() => Profile {
                     name: "Iam Git".to_string(),
                     email: "iam@example.com".to_string(),
-                    signingkey: None
+                    signingkey: None,
+                    ..Default::default()
                 },
                 "work".to_string() => Profile {
                     name: "Work Profile".to_string(),
                     email: "profile@work.com".to_string(),
                     signingkey: Some("whatever".to_string()),
+                    ..Default::default()
                 },
             },
+            person: IndexMap::new(),
+            teammate: IndexMap::new(),
+            rule: vec![],
         };
         let toml_str = toml::to_string(&config).unwrap();
         assert_eq!(
@@ -194,3 +605,307 @@ signingkey = "whatever"
         );
     }
 }
+
+#[cfg(test)]
+mod resolve {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [profile.base]
+            name = 'Foo Bar'
+            email = 'foo@bar.com'
+            signingkey = 'basekey'
+
+            [profile.work]
+            extends = 'base'
+            email = 'foo@work.com'
+
+            [profile.oss]
+            extends = 'work'
+            signingkey = '~/.ssh/oss.pub'
+            signingformat = 'ssh'
+        "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn inherits_and_overrides() -> Result<()> {
+        let config = config();
+        config.validate()?;
+
+        let (work, origins) = config.resolve_profile("work")?;
+        assert_eq!(work.name, "Foo Bar");
+        assert_eq!(work.email, "foo@work.com");
+        assert_eq!(work.signingkey.as_deref(), Some("basekey"));
+        assert_eq!(work.extends, None);
+        assert_eq!(origins["name"], "base");
+        assert_eq!(origins["email"], "work");
+        assert_eq!(origins["signingkey"], "base");
+
+        let (oss, origins) = config.resolve_profile("oss")?;
+        assert_eq!(oss.email, "foo@work.com");
+        assert_eq!(oss.signingkey.as_deref(), Some("~/.ssh/oss.pub"));
+        assert_eq!(oss.signingformat, Some(SigningFormat::Ssh));
+        assert_eq!(origins["name"], "base");
+        assert_eq!(origins["email"], "work");
+        assert_eq!(origins["signingkey"], "oss");
+        assert_eq!(origins["signingformat"], "oss");
+        Ok(())
+    }
+
+    #[test]
+    fn missing_parent() {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.work]
+            extends = 'base'
+            name = 'Foo Bar'
+            email = 'foo@work.com'
+        "#,
+        )
+        .unwrap();
+        let error = config.validate().unwrap_err();
+        assert_eq!(error.to_string(), "Profile work extends base, which doesn't exist");
+    }
+
+    #[test]
+    fn cycle() {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.a]
+            extends = 'b'
+            name = 'Foo Bar'
+            email = 'foo@bar.com'
+
+            [profile.b]
+            extends = 'a'
+        "#,
+        )
+        .unwrap();
+        let error = config.validate().unwrap_err();
+        assert_eq!(error.to_string(), "Profile a has cyclic inheritance: a -> b -> a");
+    }
+
+    #[test]
+    fn incomplete() {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.a]
+            name = 'Foo Bar'
+        "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn abstract_profiles() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.github-noreply]
+            abstract = true
+            name = '{username}'
+            email = '{username}@users.noreply.github.com'
+
+            [profile.base]
+            abstract = true
+            name = 'Foo Bar'
+
+            [profile.work]
+            extends = 'base'
+            email = 'foo@work.com'
+        "#,
+        )?;
+        config.validate()?;
+        let mut templates = config.templates();
+        templates.sort();
+        assert_eq!(templates, vec!["base".to_string(), "github-noreply".to_string()]);
+        assert!(config.is_template("base"));
+        assert!(!config.is_template("work"));
+        assert_eq!(
+            config.resolved_profiles()?.keys().collect::<Vec<_>>(),
+            vec!["work"]
+        );
+        assert!(!config.resolve_profile("work")?.0.is_abstract);
+        Ok(())
+    }
+
+    #[test]
+    fn unfilled_placeholder() {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.github-noreply]
+            abstract = true
+            name = '{username}'
+            email = '{username}@users.noreply.github.com'
+
+            [profile.alice]
+            extends = 'github-noreply'
+            name = 'Alice'
+        "#,
+        )
+        .unwrap();
+        let error = config.validate().unwrap_err();
+        assert_eq!(error.to_string(), "Profile alice has an unfilled placeholder {username}");
+    }
+
+    #[test]
+    fn rename_updates_children() {
+        let mut config = config();
+        config.rename_profile("base", "personal");
+        assert_eq!(config.profile["work"].extends.as_deref(), Some("personal"));
+        assert_eq!(config.children_of("personal"), vec!["work".to_string()]);
+        assert!(config.validate().is_ok());
+    }
+}
+
+#[cfg(test)]
+mod managed_entries {
+    use super::*;
+
+    #[test]
+    fn none() {
+        assert!(Profile::default().managed_entries().is_empty());
+    }
+
+    #[test]
+    fn ssh_identity() {
+        let profile = Profile {
+            ssh_identity: Some("/keys/work key".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            profile.managed_entries(),
+            vec![(
+                "core.sshCommand".to_string(),
+                "ssh -i '/keys/work key' -o IdentitiesOnly=yes".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn signing_format_is_inferred_from_the_key() {
+        let ssh_profile = Profile {
+            signingkey: Some("~/.ssh/id_ed25519.pub".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ssh_profile.effective_signingformat(), Some(SigningFormat::Ssh));
+
+        let explicit_profile = Profile {
+            signingformat: Some(SigningFormat::X509),
+            ..ssh_profile
+        };
+        assert_eq!(explicit_profile.effective_signingformat(), Some(SigningFormat::X509));
+        assert_eq!(Profile::default().effective_signingformat(), None);
+    }
+
+    #[test]
+    fn url_rewrites() {
+        let profile: Profile = toml::from_str(
+            r#"
+            name = "Foo Bar"
+            email = "foo@bar.com"
+
+            [[url]]
+            base = "git@github-work:"
+            insteadof = "git@github.com:"
+
+            [[url]]
+            base = "git@github-work:"
+            insteadof = "https://github.com/"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            profile.managed_entries(),
+            vec![
+                ("url.git@github-work:.insteadOf".to_string(), "git@github.com:".to_string()),
+                ("url.git@github-work:.insteadOf".to_string(), "https://github.com/".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn credentials() {
+        let profile = Profile {
+            credential: vec![Credential {
+                url: "https://github.com".to_string(),
+                username: "foo-work".to_string(),
+                helper: Some("cache".to_string()),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            profile.managed_entries(),
+            vec![
+                ("credential.https://github.com.username".to_string(), "foo-work".to_string()),
+                ("credential.https://github.com.helper".to_string(), "cache".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn credentials_scoped_to_a_path_send_it() {
+        let profile = Profile {
+            credential: vec![Credential {
+                url: "https://github.com/acme/".to_string(),
+                username: "foo-acme".to_string(),
+                helper: None,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            profile.managed_entries(),
+            vec![
+                ("credential.https://github.com/acme/.username".to_string(), "foo-acme".to_string()),
+                ("credential.https://github.com/acme/.useHttpPath".to_string(), "true".to_string()),
+            ]
+        );
+        assert!(!Credential { url: "https://github.com/".to_string(), ..Default::default() }.has_path());
+    }
+}
+
+#[cfg(test)]
+mod template {
+    use super::*;
+    use indexmap::indexmap;
+
+    fn template() -> Profile {
+        Profile {
+            is_abstract: true,
+            name: "{first} {last}".to_string(),
+            email: "{first}@{domain}".to_string(),
+            signingkey: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(template().placeholders(), vec!["first", "last", "domain"]);
+    }
+
+    #[test]
+    fn render() -> Result<()> {
+        let values = indexmap! {
+            "first".to_string() => "alice".to_string(),
+            "last".to_string() => "smith".to_string(),
+            "domain".to_string() => "example.com".to_string(),
+        };
+        let profile = template().render(&values)?;
+        assert_eq!(profile.name, "alice smith");
+        assert_eq!(profile.email, "alice@example.com");
+        assert!(!profile.is_abstract);
+        Ok(())
+    }
+
+    #[test]
+    fn render_missing_value() {
+        let values = indexmap! { "first".to_string() => "alice".to_string() };
+        let error = template().render(&values).unwrap_err();
+        assert_eq!(error.to_string(), "No value given for {last}");
+    }
+}
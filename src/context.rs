@@ -1,6 +1,21 @@
-use crate::{config::Persist, git::GitConfigClientType};
+use std::{path::Path, rc::Rc};
+
+use crate::{
+    config::Persist,
+    git::{GitConfigClient, GitConfigClientType},
+};
 
 pub struct AppContext {
     pub git_config_client: Box<dyn GitConfigClientType>,
-    pub config_client: Box<dyn Persist>,
+    pub config_client: Rc<dyn Persist>,
+}
+
+impl AppContext {
+    /// The same context, with git running in the given repository instead of the current directory
+    pub fn in_repository(&self, dir: &Path) -> AppContext {
+        AppContext {
+            git_config_client: Box::new(GitConfigClient::in_dir(dir)),
+            config_client: Rc::clone(&self.config_client),
+        }
+    }
 }
@@ -0,0 +1,127 @@
+//! git's credential helper protocol: https://git-scm.com/docs/git-credential#IOFMT
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::config::{Credential, Profile};
+
+/// Attributes exchanged with git, in the order they were received
+pub type Attributes = IndexMap<String, String>;
+
+/// Parses `key=value` lines until a blank line
+pub fn parse(input: &str) -> Attributes {
+    input
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect()
+}
+
+pub fn format(attributes: &Attributes) -> String {
+    attributes
+        .iter()
+        .map(|(key, value)| format!("{}={}\n", key, value))
+        .collect()
+}
+
+/// Rebuilds the URL git is asking credentials for
+fn url_of(attributes: &Attributes) -> Option<String> {
+    let protocol = attributes.get("protocol")?;
+    let host = attributes.get("host")?;
+    Some(match attributes.get("path") {
+        Some(path) => format!("{}://{}/{}", protocol, host, path),
+        None => format!("{}://{}", protocol, host),
+    })
+}
+
+/// Finds the credential of the profile whose URL is the longest prefix of the requested one,
+/// following git's rules for `credential.<url>.*`
+pub fn find<'a>(profile: &'a Profile, attributes: &Attributes) -> Option<&'a Credential> {
+    let url = url_of(attributes)?;
+    profile
+        .credential
+        .iter()
+        .filter(|credential| {
+            let prefix = credential.url.trim_end_matches('/');
+            url.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+        .max_by_key(|credential| credential.url.trim_end_matches('/').len())
+}
+
+/// Builds the command git would run for the given `credential.helper` value
+fn helper_command(helper: &str, operation: &str) -> Command {
+    let command_line = if let Some(shell_command) = helper.strip_prefix('!') {
+        format!("{} {}", shell_command, operation)
+    } else if helper.starts_with('/') {
+        format!("{} {}", helper, operation)
+    } else {
+        format!("git credential-{} {}", helper, operation)
+    };
+    let mut command = Command::new("sh");
+    command.args(["-c", &command_line]);
+    command
+}
+
+/// Runs the helper with the given operation and attributes, returning what it answered
+pub fn delegate(helper: &str, operation: &str, attributes: &Attributes) -> Result<Attributes> {
+    let mut child = helper_command(helper, operation)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(format(attributes).as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("Credential helper {} failed", helper);
+    }
+    Ok(parse(&String::from_utf8(output.stdout)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::indexmap;
+
+    fn profile() -> Profile {
+        Profile {
+            credential: vec![
+                Credential {
+                    url: "https://github.com".to_string(),
+                    username: "foo".to_string(),
+                    helper: None,
+                },
+                Credential {
+                    url: "https://github.com/acme/".to_string(),
+                    username: "foo-acme".to_string(),
+                    helper: None,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_and_formats() {
+        let attributes = parse("protocol=https\nhost=github.com\n\nignored=1\n");
+        assert_eq!(attributes, indexmap! {
+            "protocol".to_string() => "https".to_string(),
+            "host".to_string() => "github.com".to_string(),
+        });
+        assert_eq!(format(&attributes), "protocol=https\nhost=github.com\n");
+    }
+
+    #[test]
+    fn finds_the_most_specific_credential() {
+        let profile = profile();
+        let username = |input: &str| find(&profile, &parse(input)).map(|credential| credential.username.as_str());
+
+        assert_eq!(username("protocol=https\nhost=github.com\n"), Some("foo"));
+        assert_eq!(username("protocol=https\nhost=github.com\npath=acme/app.git\n"), Some("foo-acme"));
+        assert_eq!(username("protocol=https\nhost=github.company.com\n"), None);
+        assert_eq!(username("protocol=https\nhost=gitlab.com\n"), None);
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::Result;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Lists the directories git ignores in the given repository, as absolute paths
+fn ignored_dirs(work_tree: &Path) -> Result<HashSet<PathBuf>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(work_tree)
+        .args(["ls-files", "--others", "--ignored", "--exclude-standard", "--directory", "-z"])
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| path.ends_with('/'))
+        .map(|path| work_tree.join(path.trim_end_matches('/')))
+        .collect())
+}
+
+fn walk(dir: &Path, ignored: &mut HashSet<PathBuf>, repositories: &mut Vec<PathBuf>) -> Result<()> {
+    // `.git` is a directory in regular repositories, and a file in worktrees and submodules
+    if dir.join(".git").exists() {
+        repositories.push(dir.to_path_buf());
+        ignored.extend(ignored_dirs(dir)?);
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let mut subdirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        // Symbolic links are skipped, so are loops
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter(|entry| entry.file_name() != ".git")
+        .map(|entry| entry.path())
+        .filter(|path| !ignored.contains(path))
+        .collect();
+    subdirs.sort();
+
+    for subdir in subdirs {
+        walk(&subdir, ignored, repositories)?;
+    }
+    Ok(())
+}
+
+/// Finds the working trees of the repositories under `root`, including nested ones such as
+/// submodules and linked worktrees. Paths ignored by an enclosing repository are skipped.
+pub fn find_repositories(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut repositories = vec![];
+    walk(root, &mut HashSet::new(), &mut repositories)?;
+    Ok(repositories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git").arg("-C").arg(dir).args(args).output().unwrap().status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn finds_repositories() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let root = root.path();
+
+        let app = root.join("work/app");
+        fs::create_dir_all(&app)?;
+        git(&app, &["init", "-q"]);
+        git(&app, &["-c", "user.name=a", "-c", "user.email=a@b.c", "commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&app, &["worktree", "add", "-q", "../app-feature"]);
+
+        // Ignored by app, so never looked at
+        fs::write(app.join(".gitignore"), "vendor/\n")?;
+        fs::create_dir_all(app.join("vendor/lib"))?;
+        git(&app.join("vendor/lib"), &["init", "-q"]);
+
+        // Nested and not ignored
+        fs::create_dir_all(app.join("tools"))?;
+        git(&app.join("tools"), &["init", "-q"]);
+
+        fs::create_dir_all(root.join("notes"))?;
+
+        let repositories = find_repositories(root)?;
+        let relative: Vec<&Path> = repositories
+            .iter()
+            .map(|repository| repository.strip_prefix(root).unwrap())
+            .collect();
+        assert_eq!(
+            relative,
+            vec![Path::new("work/app"), Path::new("work/app/tools"), Path::new("work/app-feature")]
+        );
+        Ok(())
+    }
+}
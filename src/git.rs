@@ -1,9 +1,13 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
 use crate::config::{Config, Profile, ProfileMap};
-use std::process::Command;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 /// Where to store git-config values
+#[derive(Clone)]
 pub enum Level {
     /// https://git-scm.com/docs/git-config#Documentation/git-config.txt---global
     Global,
@@ -17,28 +21,55 @@ pub enum Level {
     File(String),
 }
 
-const USER_NAME: &'static str = "user.name";
-const USER_EMAIL: &'static str = "user.email";
-const USER_SIGNING_KEY: &'static str = "user.signingkey";
+const USER_NAME: &str = "user.name";
+const USER_EMAIL: &str = "user.email";
+const USER_SIGNING_KEY: &str = "user.signingkey";
+const GPG_FORMAT: &str = "gpg.format";
 
-const GET_FLAG: &'static str = "--get";
-const UNSET_FLAG: &'static str = "--unset";
+/// Records the entries set through `Profile::managed_entries`, as `<key> <value>`
+const MANAGED: &str = "gitprofile.managed";
 
-fn get_level_flag(level: &Level) -> String {
+const GET_FLAG: &str = "--get";
+const GET_ALL_FLAG: &str = "--get-all";
+const ADD_FLAG: &str = "--add";
+const UNSET_ALL_FLAG: &str = "--unset-all";
+const FIXED_VALUE_FLAG: &str = "--fixed-value";
+const UNSET_FLAG: &str = "--unset";
+const GET_REGEXP_FLAG: &str = "--get-regexp";
+
+fn get_level_flags(level: &Level) -> Vec<String> {
     match level {
-        Level::Global => "--global".to_owned(),
-        Level::System => "--system".to_owned(),
-        Level::Local => "--local".to_owned(),
-        Level::WorkTree => "--worktree".to_owned(),
-        Level::File(config_file_path) => format!("--file {config_file_path}"),
+        Level::Global => vec!["--global".to_owned()],
+        Level::System => vec!["--system".to_owned()],
+        Level::Local => vec!["--local".to_owned()],
+        Level::WorkTree => vec!["--worktree".to_owned()],
+        Level::File(config_file_path) => vec!["--file".to_owned(), config_file_path.to_owned()],
     }
 }
 
+/// A single value of a git config file
+#[derive(Debug, Eq, PartialEq)]
+pub struct ConfigEntry {
+    /// Where the value is defined, as shown by `git config --show-origin` (e.g. `file:/home/me/.gitconfig`)
+    pub origin: String,
+    /// Fully qualified key in lower case except for the subsection, e.g. `includeif.gitdir:~/work/.path`
+    pub key: String,
+    pub value: String,
+}
+
 pub trait GitConfigWrite {
     fn set(&self, profile: &Profile, maybe_level: &Option<Level>) -> Result<()>;
+    /// Runs `git config <key> <value>`
+    fn set_value(&self, key: &str, value: &str, maybe_level: &Option<Level>) -> Result<()>;
+    /// Runs `git config --unset-all <key>`, doing nothing if the key isn't set
+    fn unset(&self, key: &str, maybe_level: &Option<Level>) -> Result<()>;
 }
 
 pub trait GitConfigRead {
+    /// Gets the output of `git config --get <key>`
+    fn get_value(&self, key: &str, maybe_level: &Option<Level>) -> Result<Option<String>>;
+    /// Gets all the entries whose key matches the given regex, along with their origin
+    fn get_regexp(&self, key_pattern: &str, maybe_level: &Option<Level>) -> Result<Vec<ConfigEntry>>;
     /// Gets the output of `git config --get user.name`
     fn get_name(&self, maybe_level: &Option<Level>) -> Result<Option<String>>;
     /// Gets the output of `git config --get user.email`
@@ -49,56 +80,176 @@ pub trait GitConfigRead {
     fn get(&self, maybe_level: &Option<Level>) -> Result<Option<Profile>>;
     /// Searches for a profile key in the config file based on the current git config
     fn get_profile_key(&self, config: &Config, maybe_level: &Option<Level>) -> Result<Option<String>>;
+    /// The git directory of the repository git runs in, see `git_dir`
+    fn git_dir(&self) -> Result<Option<PathBuf>>;
+    /// The root of the working tree of the repository git runs in, see `work_tree`
+    fn work_tree(&self) -> Result<Option<PathBuf>>;
 }
 
 pub trait GitConfigClientType: GitConfigWrite + GitConfigRead {}
 impl<T> GitConfigClientType for T where T: GitConfigRead + GitConfigWrite {}
 
-pub struct GitConfigClient;
+pub struct GitConfigClient {
+    /// Where git runs, the current directory when `None`
+    dir: Option<PathBuf>,
+}
+
+impl Default for GitConfigClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl GitConfigClient {
     pub fn new() -> Self {
-        GitConfigClient {}
+        GitConfigClient { dir: None }
+    }
+
+    /// Runs git in the given repository instead of the current directory
+    pub fn in_dir(dir: &Path) -> Self {
+        GitConfigClient {
+            dir: Some(dir.to_path_buf()),
+        }
+    }
+
+    fn git_config(&self, maybe_level: &Option<Level>) -> Command {
+        let mut cmd = Command::new("git");
+        if let Some(dir) = self.dir.as_ref() {
+            cmd.arg("-C").arg(dir);
+        }
+        cmd.arg("config");
+        if let Some(level) = maybe_level {
+            cmd.args(get_level_flags(level));
+        }
+        cmd
+    }
+
+    /// Removes the entries set by the previously applied profile, and sets the given ones instead.
+    /// Entries added by the user are left alone.
+    fn replace_managed_entries(&self, entries: &[(String, String)], maybe_level: &Option<Level>) -> Result<()> {
+        // Writing without a level targets the repository config, so does the bookkeeping
+        let level = Some(maybe_level.clone().unwrap_or(Level::Local));
+
+        let output = self.git_config(&level).args([GET_ALL_FLAG, MANAGED]).output()?;
+        for record in String::from_utf8(output.stdout)?.lines() {
+            if let Some((key, value)) = record.split_once(' ') {
+                self.git_config(&level).args([UNSET_ALL_FLAG, FIXED_VALUE_FLAG, key, value]).output()?;
+            }
+        }
+        self.git_config(&level).args([UNSET_ALL_FLAG, MANAGED]).output()?;
+
+        for (key, value) in entries {
+            if is_multi_valued(key) {
+                let existing = self.git_config(&level)
+                    .args([GET_ALL_FLAG, FIXED_VALUE_FLAG, key, value])
+                    .output()?;
+                // The same entry added by the user stays theirs
+                if existing.status.success() {
+                    continue;
+                }
+                let output = self.git_config(&level).args([ADD_FLAG, key, value]).output()?;
+                if !output.status.success() {
+                    bail!("Can't set {}: {}", key, String::from_utf8_lossy(&output.stderr).trim());
+                }
+            } else {
+                self.set_value(key, value, &level)?;
+            }
+            self.git_config(&level).args([ADD_FLAG, MANAGED, &format!("{} {}", key, value)]).output()?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the key can hold several values, which are added next to the existing ones instead of replacing them
+fn is_multi_valued(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key.ends_with(".insteadof")
+        || key.ends_with(".pushinsteadof")
+        || (key.starts_with("credential.") && key.ends_with(".helper"))
+}
+
+/// Lists the levels an identity can be read from in the current repository, the last one taking precedence.
+/// The worktree level is only listed when worktrees have their own config, as it's the local one otherwise.
+pub fn identity_levels(git_config_client: &dyn GitConfigRead) -> Result<Vec<(&'static str, Level)>> {
+    let has_worktree_config = git_config_client
+        .get_value("extensions.worktreeConfig", &Some(Level::Local))?
+        .is_some_and(|value| value == "true");
+
+    let mut levels = vec![("system", Level::System), ("global", Level::Global), ("local", Level::Local)];
+    if has_worktree_config {
+        levels.push(("worktree", Level::WorkTree));
     }
+    Ok(levels)
 }
 
-fn git_config(maybe_level: &Option<Level>) -> Command {
-    let mut cmd = Command::new("git");
-    cmd.arg("config");
-    if let Some(level) = maybe_level {
-        cmd.arg(get_level_flag(&level));
+/// Runs `git rev-parse` with the given flag in the directory, or the current one, `None` outside of a repository
+fn rev_parse(maybe_dir: Option<&Path>, flag: &str) -> Result<Option<PathBuf>> {
+    let mut command = Command::new("git");
+    if let Some(dir) = maybe_dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command.args(["rev-parse", "--path-format=absolute", flag]).output()?;
+    if !output.status.success() {
+        return Ok(None);
     }
-    cmd
+    Ok(Some(PathBuf::from(String::from_utf8(output.stdout)?.trim())))
+}
+
+/// The git directory of the current repository, e.g. `/home/me/app/.git`
+pub fn git_dir() -> Result<Option<PathBuf>> {
+    rev_parse(None, "--git-dir")
+}
+
+/// The root of the working tree of the current repository
+pub fn work_tree() -> Result<Option<PathBuf>> {
+    rev_parse(None, "--show-toplevel")
+}
+
+/// The git directory shared by all the worktrees of the current repository, where its hooks live
+pub fn git_common_dir() -> Result<Option<PathBuf>> {
+    rev_parse(None, "--git-common-dir")
 }
 
 impl GitConfigWrite for GitConfigClient {
     fn set(&self, profile: &Profile, maybe_level: &Option<Level>) -> Result<()> {
-        git_config(&maybe_level).args([USER_NAME, &profile.name]).output()?;
-        git_config(&maybe_level).args([USER_EMAIL, &profile.email]).output()?;
+        self.git_config(maybe_level).args([USER_NAME, &profile.name]).output()?;
+        self.git_config(maybe_level).args([USER_EMAIL, &profile.email]).output()?;
         if let Some(signingkey) = profile.signingkey.as_ref() {
-            git_config(&maybe_level).args([USER_SIGNING_KEY, signingkey]).output()?;
+            self.git_config(maybe_level).args([USER_SIGNING_KEY, signingkey]).output()?;
         } else {
             // Unset user.signingkey in case it was set in the old config
-            git_config(&maybe_level).args([UNSET_FLAG, USER_SIGNING_KEY]).output()?;
+            self.git_config(maybe_level).args([UNSET_FLAG, USER_SIGNING_KEY]).output()?;
         }
+        // Without a key, gpg.format is left alone
+        if let Some(signingformat) = profile.effective_signingformat() {
+            self.git_config(maybe_level).args([GPG_FORMAT, signingformat.as_str()]).output()?;
+        }
+        self.replace_managed_entries(&profile.managed_entries(), maybe_level)?;
 
         Ok(())
     }
-}
 
-impl GitConfigRead for GitConfigClient {
-    fn get_name(&self, maybe_level: &Option<Level>) -> Result<Option<String>> {
-        let output = git_config(&maybe_level).args([GET_FLAG, USER_NAME]).output()?;
-        Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
+    fn set_value(&self, key: &str, value: &str, maybe_level: &Option<Level>) -> Result<()> {
+        let output = self.git_config(maybe_level).args([key, value]).output()?;
+        if !output.status.success() {
+            bail!("Can't set {}: {}", key, String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
     }
 
-    fn get_email(&self, maybe_level: &Option<Level>) -> Result<Option<String>> {
-        let output = git_config(&maybe_level).args([GET_FLAG, USER_EMAIL]).output()?;
-        Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
+    fn unset(&self, key: &str, maybe_level: &Option<Level>) -> Result<()> {
+        // Exit code 5 means there was nothing to unset
+        let output = self.git_config(maybe_level).args([UNSET_ALL_FLAG, key]).output()?;
+        if !output.status.success() && output.status.code() != Some(5) {
+            bail!("Can't unset {}: {}", key, String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
     }
+}
 
-    fn get_signingkey(&self, maybe_level: &Option<Level>) -> Result<Option<String>> {
-        let output = git_config(&maybe_level).args([GET_FLAG, USER_SIGNING_KEY]).output()?;
+impl GitConfigRead for GitConfigClient {
+    fn get_value(&self, key: &str, maybe_level: &Option<Level>) -> Result<Option<String>> {
+        let output = self.git_config(maybe_level).args([GET_FLAG, key]).output()?;
         if output.status.success() {
             Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
         } else {
@@ -106,16 +257,36 @@ impl GitConfigRead for GitConfigClient {
         }
     }
 
+    fn get_regexp(&self, key_pattern: &str, maybe_level: &Option<Level>) -> Result<Vec<ConfigEntry>> {
+        let output = self.git_config(maybe_level)
+            .args(["--show-origin", "--null", GET_REGEXP_FLAG, key_pattern])
+            .output()?;
+        Ok(parse_entries(&String::from_utf8(output.stdout)?))
+    }
+
+    fn get_name(&self, maybe_level: &Option<Level>) -> Result<Option<String>> {
+        self.get_value(USER_NAME, maybe_level)
+    }
+
+    fn get_email(&self, maybe_level: &Option<Level>) -> Result<Option<String>> {
+        self.get_value(USER_EMAIL, maybe_level)
+    }
+
+    fn get_signingkey(&self, maybe_level: &Option<Level>) -> Result<Option<String>> {
+        self.get_value(USER_SIGNING_KEY, maybe_level)
+    }
+
     fn get(&self, maybe_level: &Option<Level>) -> Result<Option<Profile>> {
-        let maybe_name = self.get_name(&maybe_level)?;
-        let maybe_email = self.get_email(&maybe_level)?;
-        let signingkey = self.get_signingkey(&maybe_level)?;
+        let maybe_name = self.get_name(maybe_level)?;
+        let maybe_email = self.get_email(maybe_level)?;
+        let signingkey = self.get_signingkey(maybe_level)?;
 
         if let (Some(name), Some(email)) = (maybe_name, maybe_email) {
             Ok(Some(Profile {
                 name,
                 email,
                 signingkey,
+                ..Default::default()
             }))
         } else {
             Ok(None)
@@ -123,13 +294,38 @@ impl GitConfigRead for GitConfigClient {
     }
 
     fn get_profile_key(&self, config: &Config, maybe_level: &Option<Level>) -> Result<Option<String>> {
-        if let Some(target) = self.get(&maybe_level).with_context(|| "Current profile not found")? {
-            let profile_key = find_profile_key(&config.profile, &target);
+        if let Some(target) = self.get(maybe_level).with_context(|| "Current profile not found")? {
+            let profile_key = find_profile_key(&config.resolved_profiles()?, &target);
             Ok(profile_key)
         } else {
             Ok(None)
         }
     }
+
+    fn git_dir(&self) -> Result<Option<PathBuf>> {
+        rev_parse(self.dir.as_deref(), "--git-dir")
+    }
+
+    fn work_tree(&self) -> Result<Option<PathBuf>> {
+        rev_parse(self.dir.as_deref(), "--show-toplevel")
+    }
+}
+
+/// Parses the output of `git config --show-origin --null`,
+/// which consists of `<origin>\0<key>\n<value>\0` records
+fn parse_entries(output: &str) -> Vec<ConfigEntry> {
+    let fields: Vec<&str> = output.split('\0').collect();
+    fields
+        .chunks_exact(2)
+        .map(|record| {
+            let (key, value) = record[1].split_once('\n').unwrap_or((record[1], ""));
+            ConfigEntry {
+                origin: record[0].to_owned(),
+                key: key.to_owned(),
+                value: value.to_owned(),
+            }
+        })
+        .collect()
 }
 
 pub fn find_profile_key(
@@ -137,7 +333,7 @@ pub fn find_profile_key(
     target: &Profile,
 ) -> Option<String> {
     profile_catalog.iter().find_map(|(profile_key, profile)| {
-        if *profile == *target {
+        if profile.same_identity(target) {
             Some(String::from(profile_key))
         } else {
             None
@@ -145,6 +341,38 @@ pub fn find_profile_key(
     })
 }
 
+#[cfg(test)]
+mod parse_entries_tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert!(parse_entries("").is_empty());
+    }
+
+    #[test]
+    fn multiple_entries() {
+        let entries = parse_entries(
+            "file:/home/me/.gitconfig\0includeif.gitdir:~/work/.path\n~/.gitconfig-work\0file:.git/config\0user.name\nGit Smith\0",
+        );
+        assert_eq!(
+            entries,
+            vec![
+                ConfigEntry {
+                    origin: "file:/home/me/.gitconfig".to_string(),
+                    key: "includeif.gitdir:~/work/.path".to_string(),
+                    value: "~/.gitconfig-work".to_string(),
+                },
+                ConfigEntry {
+                    origin: "file:.git/config".to_string(),
+                    key: "user.name".to_string(),
+                    value: "Git Smith".to_string(),
+                },
+            ]
+        );
+    }
+}
+
 #[cfg(test)]
 mod find_profile_key_tests {
     use super::*;
@@ -156,17 +384,20 @@ mod find_profile_key_tests {
             "a".to_string() => Profile {
                 name: "a".to_string(),
                 email: "a@example.com".to_string(),
-                signingkey: None
+                signingkey: None,
+                ..Default::default()
             },
             "b".to_string() => Profile {
                 name: "b".to_string(),
                 email: "b@example.com".to_string(),
-                signingkey: None
+                signingkey: None,
+                ..Default::default()
             },
             "c".to_string() => Profile {
                 name: "c".to_string(),
                 email: "c@example.com".to_string(),
-                signingkey: Some("signingkey".to_string())
+                signingkey: Some("signingkey".to_string()),
+                ..Default::default()
             }
         };
         let result_b = find_profile_key(
@@ -174,7 +405,8 @@ mod find_profile_key_tests {
             &Profile {
                 name: "b".to_string(),
                 email: "b@example.com".to_string(),
-                signingkey: None
+                signingkey: None,
+                ..Default::default()
             }
         );
         assert!(result_b.is_some());
@@ -187,17 +419,20 @@ mod find_profile_key_tests {
             "a".to_string() => Profile {
                 name: "a".to_string(),
                 email: "a@example.com".to_string(),
-                signingkey: None
+                signingkey: None,
+                ..Default::default()
             },
             "b".to_string() => Profile {
                 name: "b".to_string(),
                 email: "b@example.com".to_string(),
-                signingkey: None
+                signingkey: None,
+                ..Default::default()
             },
             "c".to_string() => Profile {
                 name: "c".to_string(),
                 email: "c@example.com".to_string(),
-                signingkey: Some("signingkey".to_string())
+                signingkey: Some("signingkey".to_string()),
+                ..Default::default()
             }
         };
         let result_a = find_profile_key(
@@ -205,7 +440,8 @@ mod find_profile_key_tests {
             &Profile {
                 name: "a".to_string(),
                 email: "b@example.com".to_string(),
-                signingkey: None
+                signingkey: None,
+                ..Default::default()
             }
         );
         assert!(result_a.is_none());
@@ -215,7 +451,8 @@ mod find_profile_key_tests {
             &Profile {
                 name: "b".to_string(),
                 email: "b@example.com".to_string(),
-                signingkey: Some("test".to_string())
+                signingkey: Some("test".to_string()),
+                ..Default::default()
             }
         );
         assert!(result_b.is_none());
@@ -225,9 +462,54 @@ mod find_profile_key_tests {
             &Profile {
                 name: "d".to_string(),
                 email: "d@example.com".to_string(),
-                signingkey: None
+                signingkey: None,
+                ..Default::default()
             }
         );
         assert!(result_d.is_none());
     }
 }
+
+#[cfg(test)]
+mod set_tests {
+    use super::*;
+
+    fn config_file() -> (tempfile::TempDir, Option<Level>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        std::fs::write(&path, "[gpg]\n\tformat = ssh\n").unwrap();
+        (dir, Some(Level::File(path.display().to_string())))
+    }
+
+    #[test]
+    fn infers_the_format_of_profiles_without_signingformat() -> Result<()> {
+        let (_dir, level) = config_file();
+        let client = GitConfigClient::new();
+        let profile = Profile {
+            name: "Foo".to_string(),
+            email: "foo@example.com".to_string(),
+            signingkey: Some("~/.ssh/id_ed25519.pub".to_string()),
+            ..Default::default()
+        };
+
+        client.set(&profile, &level)?;
+        assert_eq!(client.get_value(GPG_FORMAT, &level)?.as_deref(), Some("ssh"));
+        assert_eq!(client.get_signingkey(&level)?.as_deref(), Some("~/.ssh/id_ed25519.pub"));
+        Ok(())
+    }
+
+    #[test]
+    fn leaves_the_format_alone_without_signing_key() -> Result<()> {
+        let (_dir, level) = config_file();
+        let client = GitConfigClient::new();
+        let profile = Profile {
+            name: "Foo".to_string(),
+            email: "foo@example.com".to_string(),
+            ..Default::default()
+        };
+
+        client.set(&profile, &level)?;
+        assert_eq!(client.get_value(GPG_FORMAT, &level)?.as_deref(), Some("ssh"));
+        Ok(())
+    }
+}
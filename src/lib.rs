@@ -1,23 +1,34 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, rc::Rc};
 
-use anyhow;
-use clap::{ArgGroup, Parser, Subcommand};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand};
+use clap_complete::{engine::ArgValueCandidates, CompleteEnv};
 use commands::current::ProfileProperty;
 use config::{AppConfigClient, DEFAULT_FILE_NAME};
 use context::AppContext;
 use directories::ProjectDirs;
 use git::{GitConfigClient, Level};
+use output::OutputFormat;
 
+pub mod allowed_signers;
 pub mod commands;
 pub mod config;
 pub mod context;
+pub mod credential;
+pub mod discovery;
 pub mod git;
+pub mod mailmap;
+pub mod managed_block;
+pub mod output;
+pub mod paths;
+pub mod policy;
+pub mod prompt;
+pub mod rules;
+pub mod ssh_config;
 pub mod validation;
 
 #[derive(Parser, Debug)]
 #[clap(name = "git-profile")]
 #[clap(author, version, about = "A tool to easily configure git user profiles", long_about = None)]
-
 pub struct CliArgs {
     /// Use the given path to the configuration file to read/write profiles
     #[clap(short = 'c', long = "config-path")]
@@ -75,12 +86,18 @@ pub enum Commands {
         #[clap(long, action)]
         file: Option<String>,
     },
+    /// Show which profile is in use at each level
+    Status,
     /// List all profiles
     #[clap(group(
         ArgGroup::new("current_level")
             .args(&["system", "global", "local", "worktree", "file"]))
     )]
     List {
+        /// Also list templates (abstract profiles)
+        #[clap(short, long, action)]
+        all: bool,
+
         /// Read only from system-wide `$(prefix)/etc/gitconfig` rather than from all available files.
         #[clap(long, action)]
         system: bool,
@@ -103,38 +120,67 @@ pub enum Commands {
     },
     /// Show the details of the given profile
     Show {
-        #[clap(value_parser)]
+        #[clap(value_parser, add = ArgValueCandidates::new(commands::completions::profile_keys))]
         profile_key: String,
+
+        /// Show the effective profile after inheritance, along with where each field comes from
+        #[clap(long, action)]
+        resolved: bool,
     },
     /// Create a new profile
-    New,
+    New {
+        /// Key of the new profile
+        #[clap(value_parser)]
+        profile_key: Option<String>,
+
+        /// user.name of the new profile
+        #[clap(long)]
+        name: Option<String>,
+
+        /// user.email of the new profile
+        #[clap(long)]
+        email: Option<String>,
+
+        /// user.signingkey of the new profile
+        #[clap(long)]
+        signingkey: Option<String>,
+
+        /// Create the profile from the given template (abstract profile)
+        #[clap(short, long)]
+        template: Option<String>,
+
+        /// Fill in a placeholder of the template, e.g. `--set username=alice`
+        #[clap(long = "set", value_name = "KEY=VALUE", value_parser = parse_key_value, requires = "template")]
+        values: Vec<(String, String)>,
+    },
     /// Edit an existing profile
     Edit {
-        #[clap(value_parser)]
+        #[clap(value_parser, add = ArgValueCandidates::new(commands::completions::profile_keys))]
         profile_key: Option<String>,
     },
     /// Remove a profile
     Remove {
-        #[clap(value_parser)]
+        #[clap(value_parser, add = ArgValueCandidates::new(commands::completions::profile_keys))]
         profile_key: String,
     },
     /// Rename the given profile with the given new name
     Rename {
         /// Old profile key
-        #[clap(value_parser)]
+        #[clap(value_parser, add = ArgValueCandidates::new(commands::completions::profile_keys))]
         old: String,
         /// New profile key
         #[clap(value_parser)]
         new: String,
     },
-    /// Apply the given profile
+    /// Apply the given profile, or pick one (and where to apply it) interactively
+    #[clap(visible_alias = "switch")]
     #[clap(group(
         ArgGroup::new("apply_level")
             .args(&["global", "system", "local", "worktree", "file"]))
     )]
     Apply {
-        #[clap(value_parser)]
-        profile_key: String,
+        #[clap(value_parser, add = ArgValueCandidates::new(commands::completions::profile_keys))]
+        profile_key: Option<String>,
 
         /// Read only from system-wide `$(prefix)/etc/gitconfig` rather than from all available files.
         #[clap(long, action)]
@@ -155,6 +201,43 @@ pub enum Commands {
         /// Read only from the specified file rather than from all available files.
         #[clap(long, action)]
         file: Option<String>,
+
+        /// Apply the profile even if it violates the policy of the repository
+        #[clap(long, action)]
+        force: bool,
+    },
+    /// Apply profiles locally to every repository found under a directory
+    #[clap(group(
+        ArgGroup::new("foreach_selection")
+            .required(true)
+            .args(&["rule_based", "profile_key"]))
+    )]
+    Foreach {
+        /// Directory to look for repositories in
+        #[clap(long, value_parser)]
+        root: PathBuf,
+
+        /// Apply the profile of the first rule matching each repository
+        #[clap(long, action)]
+        rule_based: bool,
+
+        /// Apply the given profile to every repository
+        #[clap(long = "profile", add = ArgValueCandidates::new(commands::completions::profile_keys))]
+        profile_key: Option<String>,
+
+        /// Only report what would change
+        #[clap(long, action)]
+        dry_run: bool,
+    },
+    /// Report the identities used by the repositories found under a directory
+    Scan {
+        /// Directory to look for repositories in
+        #[clap(value_parser)]
+        root: PathBuf,
+
+        /// Output format. Unknown identities are only offered for import with text output
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
     },
     /// Import the current git config values as a profile
     #[clap(group(
@@ -181,6 +264,138 @@ pub enum Commands {
         /// Read only from the specified file rather than from all available files.
         #[clap(long, action)]
         file: Option<String>,
+
+        /// Import the authors of the history of a repository (default: the current one) instead
+        #[clap(long, value_name = "REPO", num_args = 0..=1, default_missing_value = ".", conflicts_with = "import_level")]
+        from_log: Option<PathBuf>,
+
+        /// Import the identities of the files included by the global git config, and their conditions as rules
+        #[clap(long, action, conflicts_with_all = ["import_level", "from_log"])]
+        from_includes: bool,
+
+        /// Import the identities of the system, global, local and worktree levels in one go
+        #[clap(long, action, conflicts_with_all = ["import_level", "from_log", "from_includes"])]
+        all_levels: bool,
+    },
+    /// Check the config file and the related git configuration for problems
+    #[clap(alias = "doctor")]
+    Check {
+        /// Output format
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Find commits made with an identity the rules or the policy of the repository don't expect
+    AuditCommits {
+        /// Commits to audit, as given to `git log`
+        #[clap(default_value = "HEAD")]
+        range: String,
+
+        /// Output format
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Rewrite the author and committer of the unpushed commits with the given profile
+    FixCommits {
+        /// Profile to rewrite the commits with
+        #[clap(short, long = "profile", add = ArgValueCandidates::new(commands::completions::profile_keys))]
+        profile_key: String,
+
+        /// Rewrite the commits since the branch forked from this one (default: the upstream branch)
+        #[clap(long)]
+        onto: Option<String>,
+
+        /// Sign the rewritten commits with the signing key of the profile
+        #[clap(long, action)]
+        sign: bool,
+
+        /// Also rewrite commits that are already on a remote-tracking branch
+        #[clap(long, action)]
+        allow_pushed: bool,
+
+        /// Replace the backup of a previous rewrite of the branch
+        #[clap(short, long, action)]
+        force: bool,
+    },
+    /// Generate an SSH key pair for the given profile and use it as its signing key
+    Keygen {
+        #[clap(value_parser, add = ArgValueCandidates::new(commands::completions::profile_keys))]
+        profile_key: String,
+
+        /// Type of the key, passed to `ssh-keygen -t`
+        #[clap(short = 't', long = "type", default_value = "ed25519", value_parser = ["ed25519", "ed25519-sk", "ecdsa", "ecdsa-sk", "rsa"])]
+        key_type: String,
+
+        /// Record the key as the signing key, the SSH identity or both
+        #[clap(short, long = "use", value_enum, default_value_t)]
+        usage: commands::keygen::KeyUsage,
+
+        /// Overwrite the key of the profile if it has already been generated
+        #[clap(short, long, action)]
+        force: bool,
+    },
+    /// Rebuild the SSH allowed signers file from the profiles
+    Sync,
+    /// Add Co-authored-by trailers for the given teammates to the commits of this repository
+    Pair {
+        /// Keys of the teammates, as listed by `git profile teammate list`
+        #[clap(required_unless_present = "clear")]
+        teammate_keys: Vec<String>,
+
+        /// End the pairing session
+        #[clap(long, action, conflicts_with = "teammate_keys")]
+        clear: bool,
+    },
+    /// Manage the roster of teammates to pair with
+    Teammate {
+        #[clap(subcommand)]
+        action: TeammateAction,
+    },
+    /// Generate the .mailmap entries mapping the profiles to the person they belong to
+    Mailmap {
+        /// Update the block managed by git-profile in the .mailmap of the repository instead of printing it
+        #[clap(short, long, action)]
+        write: bool,
+    },
+    /// Generate the ~/.ssh/config hosts matching the URL rewrites of the profiles
+    SshConfig {
+        /// Update the block managed by git-profile in the file instead of printing it
+        #[clap(short, long, action)]
+        write: bool,
+
+        /// SSH config file to update (default: ~/.ssh/config)
+        #[clap(short, long, value_parser, requires = "write")]
+        file: Option<PathBuf>,
+    },
+    /// Credential helper answering with the username of the current profile
+    Credential {
+        /// Operation requested by git: get, store or erase. Others are ignored
+        operation: String,
+    },
+    /// Block commits and pushes made with an identity that doesn't match the expected profile
+    Guard {
+        #[clap(subcommand)]
+        action: GuardAction,
+    },
+    /// Require every repository to have an explicit identity through user.useConfigOnly
+    Strict {
+        #[clap(subcommand)]
+        action: StrictAction,
+    },
+    /// Run a hook installed by `guard` or `pair`
+    Hook {
+        #[clap(value_parser = commands::guard::HOOK_NAMES)]
+        hook_name: String,
+
+        /// Arguments given by git, passed on to the chained hook
+        #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Manage profiles from a full-screen dashboard
+    Tui,
+    /// Print the script enabling the completions of the given shell
+    Completions {
+        #[clap(value_parser = commands::completions::SHELLS)]
+        shell: String,
     },
     /// Dump the content of the config file
     ConfigDump,
@@ -188,9 +403,52 @@ pub enum Commands {
     ConfigPath,
 }
 
-fn get_default_path() -> PathBuf {
+#[derive(Subcommand, Debug)]
+pub enum GuardAction {
+    /// Install hooks checking the identity through core.hooksPath, forwarding to the hooks they replace
+    Install {
+        /// Install them for every repository instead of the current one
+        #[clap(long, action)]
+        global: bool,
+    },
+    /// Remove the hooks and restore the previous core.hooksPath
+    Uninstall {
+        /// Uninstall the global hooks instead of the ones of the current repository
+        #[clap(long, action)]
+        global: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TeammateAction {
+    /// Add a teammate to the roster
+    Add {
+        /// Key to pair with the teammate, as in `git profile pair <key>`
+        key: String,
+
+        /// Name of the teammate
+        #[clap(long)]
+        name: Option<String>,
+
+        /// Email of the teammate
+        #[clap(long)]
+        email: Option<String>,
+    },
+    /// List the teammates of the roster
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StrictAction {
+    /// Set user.useConfigOnly globally, optionally moving the global identity into a profile
+    Enable,
+    /// Unset user.useConfigOnly and restore the global identity
+    Disable,
+}
+
+pub(crate) fn get_default_path() -> PathBuf {
     let project_dirs = ProjectDirs::from("org", "git-profile", "git-profile").unwrap();
-    PathBuf::from(project_dirs.config_dir().join(DEFAULT_FILE_NAME))
+    project_dirs.config_dir().join(DEFAULT_FILE_NAME)
 }
 
 fn get_config_path(args: &CliArgs) -> PathBuf {
@@ -201,6 +459,13 @@ fn get_config_path(args: &CliArgs) -> PathBuf {
     }
 }
 
+fn parse_key_value(input: &str) -> Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((key, value)) => Ok((key.trim().to_owned(), value.to_owned())),
+        None => Err(format!("Expected KEY=VALUE but got {}", input)),
+    }
+}
+
 fn get_level(
     system: bool,
     global: bool,
@@ -219,13 +484,17 @@ fn get_level(
 }
 
 pub fn run() -> anyhow::Result<()> {
+    CompleteEnv::with_factory(CliArgs::command)
+        .var(commands::completions::COMPLETE_VAR)
+        .complete();
+
     let args = CliArgs::parse();
 
     let config_path = get_config_path(&args);
 
     let context = AppContext {
         git_config_client: Box::new(GitConfigClient::new()),
-        config_client: Box::new(AppConfigClient::new(config_path.clone())),
+        config_client: Rc::new(AppConfigClient::new(config_path.clone())),
     };
 
     match args.command {
@@ -248,7 +517,7 @@ pub fn run() -> anyhow::Result<()> {
                 (_, true, _, _, _) => ProfileProperty::Email,
                 (_, _, true, _, _) => ProfileProperty::SigningKey,
                 (_, _, _, true, _) => ProfileProperty::Profile,
-                (_, _, _, _, true) | _ => ProfileProperty::ProfileKey,
+                _ => ProfileProperty::ProfileKey,
             };
             commands::current::execute(
                 &context,
@@ -257,6 +526,7 @@ pub fn run() -> anyhow::Result<()> {
             )?;
         }
         Commands::List {
+            all,
             // Which level to retrieve from
             system,
             global,
@@ -266,14 +536,33 @@ pub fn run() -> anyhow::Result<()> {
         } => {
             commands::list::execute(
                 &context,
+                all,
                 &get_level(system, global, local, worktree, file)
             )?;
         }
-        Commands::Show { profile_key } => {
-            commands::show::execute(&context, &profile_key)?;
+        Commands::Status => {
+            commands::status::execute(&context)?;
         }
-        Commands::New {} => {
-            commands::new::execute(&context)?;
+        Commands::Show { profile_key, resolved } => {
+            commands::show::execute(&context, &profile_key, resolved)?;
+        }
+        Commands::New {
+            profile_key,
+            name,
+            email,
+            signingkey,
+            template,
+            values,
+        } => {
+            let args = commands::new::NewProfileArgs {
+                profile_key,
+                name,
+                email,
+                signingkey,
+                template,
+                values: values.into_iter().collect(),
+            };
+            commands::new::execute(&context, &args)?;
         }
         Commands::Edit { profile_key } => {
             commands::edit::execute(&context, &profile_key)?;
@@ -294,29 +583,106 @@ pub fn run() -> anyhow::Result<()> {
             local,
             worktree,
             file,
+            force,
         } => {
             commands::apply::execute(
                 &context,
-                &profile_key,
-                &get_level(system, global, local, worktree, file)
+                profile_key.as_deref(),
+                &get_level(system, global, local, worktree, file),
+                force,
             )?;
         }
+        Commands::Foreach { root, rule_based: _, profile_key, dry_run } => {
+            let selection = match profile_key {
+                Some(profile_key) => commands::foreach::Selection::Profile(profile_key),
+                None => commands::foreach::Selection::RuleBased,
+            };
+            commands::foreach::execute(&context, &root, &selection, dry_run)?;
+        }
+        Commands::Scan { root, format } => {
+            commands::scan::execute(&context, &root, format)?;
+        }
         Commands::Import {
             system,
             global,
             local,
             worktree,
             file,
+            from_log,
+            from_includes,
+            all_levels,
         } => {
-            commands::import::execute(
-                &context, 
-                &get_level(system, global, local, worktree, file)
-            )?;
+            if all_levels {
+                commands::import::execute_all_levels(&context)?;
+            } else if from_includes {
+                commands::import::execute_from_includes(&context)?;
+            } else if let Some(repository) = from_log {
+                commands::import::execute_from_log(&context, &repository)?;
+            } else {
+                commands::import::execute(
+                    &context,
+                    &get_level(system, global, local, worktree, file)
+                )?;
+            }
+        },
+        Commands::Check { format } => {
+            commands::check::execute(&context, format)?;
+        }
+        Commands::AuditCommits { range, format } => {
+            commands::audit_commits::execute(&context, &range, format)?;
+        }
+        Commands::FixCommits { profile_key, onto, sign, allow_pushed, force } => {
+            commands::fix_commits::execute(&context, &profile_key, onto.as_deref(), sign, allow_pushed, force)?;
+        }
+        Commands::Keygen { profile_key, key_type, usage, force } => {
+            commands::keygen::execute(&context, &profile_key, &key_type, usage, force)?;
+        }
+        Commands::Sync => {
+            commands::sync::execute(&context)?;
+        }
+        Commands::Pair { teammate_keys, clear } => {
+            if clear {
+                commands::pair::clear(&context)?;
+            } else {
+                commands::pair::start(&context, &teammate_keys)?;
+            }
+        }
+        Commands::Teammate { action } => match action {
+            TeammateAction::Add { key, name, email } => {
+                commands::teammate::add(&context, &key, name.as_deref(), email.as_deref())?
+            }
+            TeammateAction::List => commands::teammate::list(&context)?,
+        },
+        Commands::Mailmap { write } => {
+            commands::mailmap::execute(&context, write)?;
+        }
+        Commands::SshConfig { write, file } => {
+            commands::ssh_config::execute(&context, write, file)?;
+        }
+        Commands::Credential { operation } => {
+            commands::credential::execute(&context, &operation)?;
+        }
+        Commands::Guard { action } => match action {
+            GuardAction::Install { global } => commands::guard::install(&context, global)?,
+            GuardAction::Uninstall { global } => commands::guard::uninstall(&context, global)?,
+        },
+        Commands::Strict { action } => match action {
+            StrictAction::Enable => commands::strict::enable(&context)?,
+            StrictAction::Disable => commands::strict::disable(&context)?,
         },
-        Commands::ConfigDump {} => {
-            commands::config_dump::execute(&config_path.to_str().unwrap())?;
+        Commands::Hook { hook_name, args } => {
+            commands::hook::execute(&context, &hook_name, &args)?;
+        }
+        Commands::Tui => {
+            commands::tui::execute(&context)?;
+        }
+        Commands::Completions { shell } => {
+            commands::completions::execute(&shell)?;
+        }
+        Commands::ConfigDump => {
+            commands::config_dump::execute(config_path.to_str().unwrap())?;
         },
-        Commands::ConfigPath {} => {
+        Commands::ConfigPath => {
             println!("{}", &config_path.display());
         }
     }
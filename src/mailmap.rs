@@ -0,0 +1,89 @@
+use anyhow::Result;
+use std::{fs, path::Path};
+
+use crate::{config::Config, managed_block};
+
+pub const FILE_NAME: &str = ".mailmap";
+
+const BEGIN: &str = "# BEGIN git-profile. Run `git profile mailmap --write` to update.";
+const END: &str = "# END git-profile";
+
+/// Renders the entries mapping the identity of every profile to the canonical one of its person
+pub fn render(config: &Config) -> Result<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for (_, profile) in config.resolved_profiles()? {
+        let Some(person) = profile.person.as_ref().and_then(|person| config.person.get(person)) else {
+            continue;
+        };
+        let line = if profile.email == person.email {
+            if profile.name == person.name {
+                continue;
+            }
+            // Only the name needs to be fixed
+            format!("{} <{}>", person.name, person.email)
+        } else {
+            format!("{} <{}> <{}>", person.name, person.email, profile.email)
+        };
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+/// Updates the git-profile block of the given .mailmap, returning the number of entries
+pub fn write(config: &Config, path: &Path) -> Result<usize> {
+    let block = render(config)?;
+    // A missing file is simply empty
+    let content = fs::read_to_string(path).unwrap_or_default();
+    fs::write(path, managed_block::replace(&content, BEGIN, END, &block))?;
+    Ok(block.lines().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_profiles_to_their_person() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            [person.foo]
+            name = "Foo Bar"
+            email = "foo@bar.com"
+
+            [profile.personal]
+            name = "foo"
+            email = "foo@bar.com"
+            person = "foo"
+
+            [profile.work]
+            name = "Foo Bar"
+            email = "foo@work.com"
+            person = "foo"
+
+            [profile.oss]
+            extends = "work"
+            email = "foo@users.noreply.github.com"
+
+            [profile.other]
+            name = "Someone Else"
+            email = "else@work.com"
+            "#,
+        )?;
+        // Tables aren't deserialized in file order
+        let mut lines: Vec<String> = render(&config)?.lines().map(str::to_owned).collect();
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                "Foo Bar <foo@bar.com>",
+                "Foo Bar <foo@bar.com> <foo@users.noreply.github.com>",
+                "Foo Bar <foo@bar.com> <foo@work.com>",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+//! Blocks of generated lines kept between marker comments inside files that are also edited by hand
+
+/// Replaces the lines between `begin` and `end` (markers included) with the given block,
+/// or appends it when the markers are missing. An empty block removes the markers too.
+pub fn replace(content: &str, begin: &str, end: &str, block: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let begin_index = lines.iter().position(|line| line.trim() == begin);
+    let end_index = begin_index.and_then(|begin_index| {
+        lines[begin_index..]
+            .iter()
+            .position(|line| line.trim() == end)
+            .map(|offset| begin_index + offset)
+    });
+
+    let managed: Vec<String> = if block.is_empty() {
+        vec![]
+    } else {
+        let mut managed = vec![begin.to_owned()];
+        managed.extend(block.lines().map(str::to_owned));
+        managed.push(end.to_owned());
+        managed
+    };
+
+    let mut result: Vec<String> = match (begin_index, end_index) {
+        (Some(begin_index), Some(end_index)) => {
+            let mut result: Vec<String> = lines[..begin_index].iter().map(|line| line.to_string()).collect();
+            result.extend(managed);
+            result.extend(lines[end_index + 1..].iter().map(|line| line.to_string()));
+            result
+        }
+        _ => {
+            let mut result: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+            if !managed.is_empty() && result.last().is_some_and(|line| !line.trim().is_empty()) {
+                result.push(String::new());
+            }
+            result.extend(managed);
+            result
+        }
+    };
+
+    while result.last().is_some_and(|line| line.trim().is_empty()) {
+        result.pop();
+    }
+    if result.is_empty() {
+        String::new()
+    } else {
+        result.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEGIN: &str = "# BEGIN";
+    const END: &str = "# END";
+
+    #[test]
+    fn appends() {
+        assert_eq!(replace("", BEGIN, END, "a\nb"), "# BEGIN\na\nb\n# END\n");
+        assert_eq!(replace("mine\n", BEGIN, END, "a"), "mine\n\n# BEGIN\na\n# END\n");
+    }
+
+    #[test]
+    fn replaces_and_keeps_the_rest() {
+        let content = "before\n# BEGIN\nold\n# END\nafter\n";
+        assert_eq!(replace(content, BEGIN, END, "new"), "before\n# BEGIN\nnew\n# END\nafter\n");
+    }
+
+    #[test]
+    fn removes() {
+        let content = "before\n\n# BEGIN\nold\n# END\n";
+        assert_eq!(replace(content, BEGIN, END, ""), "before\n");
+    }
+}
@@ -0,0 +1,11 @@
+use clap::ValueEnum;
+
+/// How reports are printed
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// JSON, for other tools to consume
+    Json,
+}
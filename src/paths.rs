@@ -0,0 +1,59 @@
+use directories::BaseDirs;
+use std::path::{Path, PathBuf};
+
+/// Expands a leading `~/` to the home directory, like git does for paths in its config
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), BaseDirs::new()) {
+        (Some(rest), Some(base_dirs)) => base_dirs.home_dir().join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Resolves a path found in a git config file: `~/` is expanded,
+/// and relative paths are relative to the directory of that file
+pub fn resolve_config_path(path: &str, config_file: &Path) -> PathBuf {
+    let expanded = expand_home(path);
+    if expanded.is_absolute() {
+        expanded
+    } else {
+        config_file
+            .parent()
+            .map(|dir| dir.join(&expanded))
+            .unwrap_or(expanded)
+    }
+}
+
+/// Resolves a file managed by git-profile, which lives next to the config file.
+/// The path is made absolute as it may end up in the git config.
+pub fn next_to_config(config_path: &Path, name: &str) -> PathBuf {
+    let config_path = std::path::absolute(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+    config_path
+        .parent()
+        .map(|dir| dir.join(name))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_home() {
+        let home = BaseDirs::new().unwrap().home_dir().to_path_buf();
+        assert_eq!(expand_home("~/.ssh/id_ed25519"), home.join(".ssh/id_ed25519"));
+        assert_eq!(expand_home("/etc/gitconfig"), PathBuf::from("/etc/gitconfig"));
+        assert_eq!(expand_home("relative/~/path"), PathBuf::from("relative/~/path"));
+    }
+
+    #[test]
+    fn resolves_relative_to_config_file() {
+        assert_eq!(
+            resolve_config_path("gitconfig-work", Path::new("/home/me/.gitconfig")),
+            PathBuf::from("/home/me/gitconfig-work")
+        );
+        assert_eq!(
+            resolve_config_path("/etc/gitconfig-work", Path::new("/home/me/.gitconfig")),
+            PathBuf::from("/etc/gitconfig-work")
+        );
+    }
+}
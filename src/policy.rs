@@ -0,0 +1,143 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+use crate::{
+    config::{Profile, SigningFormat},
+    git::GitConfigRead,
+};
+
+/// Committed at the root of a repository to constrain the profiles used in it
+pub const FILE_NAME: &str = ".gitprofile-policy.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Regex the email must match, e.g. `@acme\.com$`
+    pub email: Option<String>,
+    /// Commits must be signed with this format
+    pub signingformat: Option<SigningFormat>,
+    /// Keys of the profiles allowed in the repository, any if empty
+    #[serde(default)]
+    pub profiles: Vec<String>,
+}
+
+impl Policy {
+    /// Reads the policy of the repository at `work_tree`, if it has one
+    pub fn load(work_tree: &Path) -> Result<Option<Policy>> {
+        let path = work_tree.join(FILE_NAME);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Ok(None);
+        };
+        let policy: Policy = toml::from_str(&content).with_context(|| format!("Invalid policy in {}", path.display()))?;
+        if let Some(email) = policy.email.as_ref() {
+            Regex::new(email).with_context(|| format!("Invalid email pattern in {}", path.display()))?;
+        }
+        Ok(Some(policy))
+    }
+
+    /// Reads the policy of the repository git runs in, if any
+    pub fn current(git_config_client: &dyn GitConfigRead) -> Result<Option<Policy>> {
+        match git_config_client.work_tree()? {
+            Some(work_tree) => Policy::load(&work_tree),
+            None => Ok(None),
+        }
+    }
+
+    /// Lists how the given resolved profile breaks the policy
+    pub fn violations(&self, profile_key: &str, profile: &Profile) -> Vec<String> {
+        let mut violations = vec![];
+        if !self.profiles.is_empty() && !self.profiles.iter().any(|allowed| allowed == profile_key) {
+            violations.push(format!("only {} can be used", self.profiles.join(", ")));
+        }
+        if let Some(email) = self.email.as_ref() {
+            // Checked by `load`
+            let regex = Regex::new(email).unwrap();
+            if !regex.is_match(&profile.email) {
+                violations.push(format!("{} doesn't match {}", profile.email, email));
+            }
+        }
+        if let Some(signingformat) = self.signingformat {
+            if profile.signingkey.is_none() {
+                violations.push(format!("commits must be signed with {}", signingformat.as_str()));
+            } else {
+                // Applying the profile sets the format of its key when it has no explicit one
+                let actual = profile.effective_signingformat().unwrap_or(SigningFormat::Openpgp);
+                if actual != signingformat {
+                    violations.push(format!(
+                        "commits must be signed with {}, not {}",
+                        signingformat.as_str(),
+                        actual.as_str()
+                    ));
+                }
+            }
+        }
+        violations
+    }
+
+    /// Fails when the profile breaks the policy, or only warns about it when forced
+    pub fn enforce(&self, profile_key: &str, profile: &Profile, force: bool) -> Result<()> {
+        let violations = self.violations(profile_key, profile);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        let message = format!("Profile {} violates {}: {}", profile_key, FILE_NAME, violations.join("; "));
+        if !force {
+            bail!("{}", message);
+        }
+        println!("⚠️  {}", message);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> Policy {
+        toml::from_str(
+            r#"
+            email = '@acme\.com$'
+            signingformat = "ssh"
+            profiles = ["work", "work-oss"]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn accepts_compliant_profiles() {
+        let profile = Profile {
+            email: "foo@acme.com".to_string(),
+            signingkey: Some("~/.ssh/work.pub".to_string()),
+            signingformat: Some(SigningFormat::Ssh),
+            ..Default::default()
+        };
+        assert!(policy().violations("work", &profile).is_empty());
+
+        let inferred_profile = Profile {
+            signingformat: None,
+            ..profile
+        };
+        assert!(policy().violations("work", &inferred_profile).is_empty());
+        assert!(Policy::default().violations("home", &Profile::default()).is_empty());
+    }
+
+    #[test]
+    fn lists_violations() {
+        let profile = Profile {
+            email: "foo@acme.com.evil.org".to_string(),
+            signingkey: Some("ABCD1234".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy().violations("home", &profile),
+            vec![
+                "only work, work-oss can be used",
+                r"foo@acme.com.evil.org doesn't match @acme\.com$",
+                "commits must be signed with ssh, not openpgp",
+            ]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+use dialoguer::{theme::ColorfulTheme, FuzzySelect, Input, Select};
+
+use crate::{
+    config::{Config, ProfileMap, SigningFormat},
+    git::{self, GitConfigRead, Level},
+    validation::{self, signing_key},
+};
+
+/// Asks for the key of a new profile, which must not be in use yet
+pub fn input_profile_key(profile_catalog: &ProfileMap) -> Result<String> {
+    let profile_key: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Enter profile name")
+        .validate_with(|input: &String| -> Result<()> {
+            validation::profile_key::no_duplicates(input, profile_catalog)?;
+            Ok(())
+        })
+        .interact_text()?;
+    Ok(profile_key)
+}
+
+/// Lets the user pick one of the signing keys found on this machine for the given email,
+/// or enter one manually. The format is derived from the chosen key.
+pub fn select_signingkey(email: &str) -> Result<(String, SigningFormat)> {
+    let candidates = signing_key::discover(email);
+
+    if !candidates.is_empty() {
+        let mut items: Vec<&str> = candidates
+            .iter()
+            .map(|candidate| candidate.description.as_str())
+            .collect();
+        items.push("Enter a signing key manually");
+
+        let selected_index = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select a signing key (user.signingkey)")
+            .items(&items)
+            .default(0)
+            .interact()?;
+
+        if let Some(candidate) = candidates.get(selected_index) {
+            return Ok((candidate.signingkey.to_owned(), candidate.format));
+        }
+    }
+
+    let signingkey: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Enter signing key (user.singingkey)")
+        .interact_text()?;
+    let format = signing_key::format_of(&signingkey);
+
+    Ok((signingkey, format))
+}
+
+/// Lets the user search the given profiles by key, name or email and pick one,
+/// starting on the current profile if any
+pub fn select_profile_key(
+    config: &Config,
+    profile_keys: &[String],
+    prompt: &str,
+    current_profile_key: Option<&str>,
+) -> Result<String> {
+    let items = describe_profiles(config, profile_keys, current_profile_key)?;
+    let default_index = current_profile_key
+        .and_then(|current| profile_keys.iter().position(|profile_key| profile_key == current))
+        .unwrap_or(0);
+
+    let selected_index = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(&items)
+        .default(default_index)
+        .interact()?;
+
+    Ok(profile_keys[selected_index].to_owned())
+}
+
+/// Lets the user pick the level to write to, starting on the repository when in one
+pub fn select_level(git_config_client: &dyn GitConfigRead) -> Result<Level> {
+    let in_repository = git::git_dir()?.is_some();
+    let levels: Vec<(&str, Level)> = git::identity_levels(git_config_client)?
+        .into_iter()
+        .filter(|(_, level)| in_repository || !matches!(level, Level::Local | Level::WorkTree))
+        .collect();
+    let default_name = if in_repository { "local" } else { "global" };
+    let default_index = levels.iter().position(|(name, _)| *name == default_name).unwrap_or(0);
+
+    let names: Vec<&str> = levels.iter().map(|(name, _)| *name).collect();
+    let selected_index = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Select where to apply it")
+        .items(&names)
+        .default(default_index)
+        .interact()?;
+
+    Ok(levels[selected_index].1.clone())
+}
+
+/// Describes each profile on one line, with its name, email and signing status aligned in columns
+pub fn describe_profiles(
+    config: &Config,
+    profile_keys: &[String],
+    current_profile_key: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut rows = vec![];
+    for profile_key in profile_keys {
+        let (profile, _) = config.resolve_profile(profile_key)?;
+        let signing = match profile.signingkey.as_ref().and(profile.effective_signingformat()) {
+            Some(format) => format!("signed ({})", format.as_str()),
+            None => "unsigned".to_owned(),
+        };
+        let mut notes = vec![];
+        if config.is_template(profile_key) {
+            notes.push("template");
+        }
+        if current_profile_key == Some(profile_key.as_str()) {
+            notes.push("current");
+        }
+        rows.push([profile_key.to_owned(), profile.name, profile.email, signing, notes.join(", ")]);
+    }
+
+    let key_width = rows.iter().map(|row| row[0].chars().count()).max().unwrap_or(0);
+    let name_width = rows.iter().map(|row| row[1].chars().count()).max().unwrap_or(0);
+    let email_width = rows.iter().map(|row| row[2].chars().count()).max().unwrap_or(0);
+    let signing_width = rows.iter().map(|row| row[3].chars().count()).max().unwrap_or(0);
+    let lines = rows
+        .into_iter()
+        .map(|[profile_key, name, email, signing, notes]| {
+            let line = format!(
+                "{:key_width$}  {:name_width$}  {:email_width$}  {:signing_width$}  {}",
+                profile_key, name, email, signing, notes
+            );
+            line.trim_end().to_owned()
+        })
+        .collect();
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_profiles_in_columns() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.work]
+            name = 'Foo Bar'
+            email = 'foo@work.com'
+            signingkey = '~/.ssh/work.pub'
+            signingformat = 'ssh'
+
+            [profile.oss]
+            name = 'Foo'
+            email = 'foo@oss.org'
+            signingkey = 'ABCD1234'
+
+            [profile.company]
+            abstract = true
+            name = '{name}'
+            email = '{username}@company.com'
+        "#,
+        )?;
+        let profile_keys: Vec<String> = ["work", "oss", "company"].map(str::to_owned).to_vec();
+
+        let lines = describe_profiles(&config, &profile_keys, Some("oss"))?;
+        assert_eq!(
+            lines,
+            vec![
+                "work     Foo Bar  foo@work.com            signed (ssh)",
+                "oss      Foo      foo@oss.org             signed (openpgp)  current",
+                "company  {name}   {username}@company.com  unsigned          template",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+use std::path::Path;
+
+use crate::{
+    config::{Config, Rule},
+    paths::expand_home,
+};
+
+lazy_static! {
+    /// Tokens of a gitdir pattern that aren't matched literally
+    static ref WILDCARD: Regex = Regex::new(r"\*\*/|\*\*|\*|\?").unwrap();
+}
+
+/// Converts a gitdir pattern to a regex, the way git evaluates `includeIf "gitdir:..."`:
+/// `~/` is expanded, a relative pattern can match at any depth,
+/// and a trailing `/` matches everything inside the directory.
+fn to_regex(pattern: &str, ignore_case: bool) -> Regex {
+    let mut pattern = expand_home(pattern).display().to_string();
+    if !pattern.starts_with('/') {
+        pattern = format!("**/{}", pattern);
+    }
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+
+    let mut regex = String::from("^");
+    let mut last = 0;
+    for wildcard in WILDCARD.find_iter(&pattern) {
+        regex.push_str(&regex::escape(&pattern[last..wildcard.start()]));
+        regex.push_str(match wildcard.as_str() {
+            "**/" => "(.*/)?",
+            "**" => ".*",
+            "*" => "[^/]*",
+            _ => "[^/]",
+        });
+        last = wildcard.end();
+    }
+    regex.push_str(&regex::escape(&pattern[last..]));
+    regex.push('$');
+
+    RegexBuilder::new(&regex).case_insensitive(ignore_case).build().unwrap()
+}
+
+/// Whether the git directory (e.g. `/home/me/work/app/.git`) matches the gitdir pattern
+pub fn matches(pattern: &str, ignore_case: bool, git_dir: &Path) -> bool {
+    let git_dir = git_dir.display().to_string();
+    let regex = to_regex(pattern, ignore_case);
+    // A pattern naming the worktree itself also matches the `.git` inside it
+    regex.is_match(&git_dir) || git_dir.strip_suffix("/.git").is_some_and(|work_dir| regex.is_match(work_dir))
+}
+
+/// The first rule matching the git directory
+pub fn rule_for<'a>(config: &'a Config, git_dir: &Path) -> Option<&'a Rule> {
+    config.rule.iter().find(|rule| matches(&rule.gitdir, rule.ignore_case, git_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_like_git() {
+        let git_dir = Path::new("/src/work/app/.git");
+        assert!(matches("/src/work/", false, git_dir));
+        assert!(matches("/src/*/app/", false, git_dir));
+        assert!(matches("/src/**/.git", false, git_dir));
+        assert!(matches("work/app/", false, git_dir));
+        assert!(matches("/src/work/app", false, git_dir));
+        assert!(!matches("/src/oss/", false, git_dir));
+        assert!(!matches("/src/*/", false, Path::new("/src-other/app/.git")));
+        assert!(!matches("/src/w?rk.*/", false, git_dir));
+    }
+
+    #[test]
+    fn matches_ignoring_case() {
+        let git_dir = Path::new("/src/Work/App/.git");
+        assert!(!matches("/src/work/", false, git_dir));
+        assert!(matches("/src/work/", true, git_dir));
+        assert!(matches("/SRC/*/app", true, git_dir));
+    }
+
+    #[test]
+    fn first_rule_wins() {
+        let config: Config = toml::from_str(
+            r#"
+            [[rule]]
+            gitdir = "/src/work/oss/"
+            profile = "oss"
+
+            [[rule]]
+            gitdir = "/src/work/"
+            profile = "work"
+            "#,
+        )
+        .unwrap();
+        let profile_of = |git_dir: &str| rule_for(&config, Path::new(git_dir)).map(|rule| rule.profile.as_str());
+        assert_eq!(profile_of("/src/work/oss/lib/.git"), Some("oss"));
+        assert_eq!(profile_of("/src/work/app/.git"), Some("work"));
+        assert_eq!(profile_of("/src/home/.git"), None);
+    }
+}
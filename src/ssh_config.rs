@@ -0,0 +1,130 @@
+use anyhow::Result;
+use std::{fs, path::{Path, PathBuf}};
+
+use crate::{config::Config, managed_block, paths::expand_home};
+
+const BEGIN: &str = "# BEGIN git-profile. Run `git profile ssh-config --write` to update.";
+const END: &str = "# END git-profile";
+
+/// The SSH config file of the user
+pub fn default_path() -> PathBuf {
+    expand_home("~/.ssh/config")
+}
+
+/// Extracts the user and the host of an SSH URL, either `ssh://[user@]host[:port]/path`
+/// or the scp-like `[user@]host:path`. Other URLs have no SSH host.
+fn ssh_host_of(url: &str) -> Option<(Option<&str>, &str)> {
+    let authority = if let Some(rest) = url.strip_prefix("ssh://") {
+        let authority = rest.split('/').next()?;
+        authority.rsplit_once(':').map(|(authority, _)| authority).unwrap_or(authority)
+    } else if url.contains("://") {
+        return None;
+    } else {
+        url.split_once(':')?.0
+    };
+    let (user, host) = match authority.split_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, authority),
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some((user, host))
+}
+
+/// Renders a `Host` block for every URL rewrite pointing at a host alias, so that the alias
+/// connects to the real host with the SSH identity of the profile.
+/// Returns the block along with the rewrites that were skipped and why.
+pub fn render(config: &Config) -> Result<(String, Vec<String>)> {
+    let mut aliases: Vec<String> = vec![];
+    let mut blocks: Vec<String> = vec![];
+    let mut warnings = vec![];
+
+    for (key, profile) in config.resolved_profiles()? {
+        for rewrite in profile.url.iter() {
+            let (Some((_, alias)), Some((user, hostname))) = (ssh_host_of(&rewrite.base), ssh_host_of(&rewrite.insteadof))
+            else {
+                continue;
+            };
+            if alias == hostname || aliases.iter().any(|known| known == alias) {
+                continue;
+            }
+            let Some(ssh_identity) = profile.ssh_identity.as_ref() else {
+                warnings.push(format!("Skipped {} of {}: the profile has no ssh_identity", alias, key));
+                continue;
+            };
+
+            let mut lines = vec![format!("Host {}", alias), format!("    HostName {}", hostname)];
+            if let Some(user) = user {
+                lines.push(format!("    User {}", user));
+            }
+            lines.push(format!("    IdentityFile {}", ssh_identity));
+            lines.push("    IdentitiesOnly yes".to_owned());
+
+            aliases.push(alias.to_owned());
+            blocks.push(lines.join("\n"));
+        }
+    }
+
+    Ok((blocks.join("\n\n"), warnings))
+}
+
+/// Updates the git-profile block of the given SSH config file, returning the number of hosts along with warnings
+pub fn write(config: &Config, path: &Path) -> Result<(usize, Vec<String>)> {
+    let (block, warnings) = render(config)?;
+    // A missing file is simply empty
+    let content = fs::read_to_string(path).unwrap_or_default();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, managed_block::replace(&content, BEGIN, END, &block))?;
+    Ok((block.lines().filter(|line| line.starts_with("Host ")).count(), warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ssh_hosts() {
+        assert_eq!(ssh_host_of("git@github.com:"), Some((Some("git"), "github.com")));
+        assert_eq!(ssh_host_of("github-work:"), Some((None, "github-work")));
+        assert_eq!(ssh_host_of("ssh://git@github.com:22/"), Some((Some("git"), "github.com")));
+        assert_eq!(ssh_host_of("https://github.com/"), None);
+    }
+
+    #[test]
+    fn renders_host_blocks() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            [profile.work]
+            name = "Foo Bar"
+            email = "foo@work.com"
+            ssh_identity = "~/.ssh/work"
+
+            [[profile.work.url]]
+            base = "git@github-work:"
+            insteadof = "git@github.com:"
+
+            [[profile.work.url]]
+            base = "https://github.com/work/"
+            insteadof = "https://github.com/"
+
+            [profile.oss]
+            name = "Foo Bar"
+            email = "foo@oss.com"
+
+            [[profile.oss.url]]
+            base = "git@github-oss:"
+            insteadof = "git@github.com:"
+            "#,
+        )?;
+        let (block, warnings) = render(&config)?;
+        assert_eq!(
+            block,
+            "Host github-work\n    HostName github.com\n    User git\n    IdentityFile ~/.ssh/work\n    IdentitiesOnly yes"
+        );
+        assert_eq!(warnings, vec!["Skipped github-oss of oss: the profile has no ssh_identity"]);
+        Ok(())
+    }
+}
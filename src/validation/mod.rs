@@ -1,2 +1,3 @@
 pub mod email;
 pub mod profile_key;
+pub mod signing_key;
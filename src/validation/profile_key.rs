@@ -10,6 +10,15 @@ pub fn no_duplicates(input: &str, profile_catalog: &ProfileMap) -> Result<()> {
     }
 }
 
+/// Checks that the key can be used as a file name, without escaping the directory it's joined to
+pub fn is_file_name(input: &str) -> Result<()> {
+    if input.is_empty() || input == "." || input.contains("..") || input.contains(['/', '\\']) {
+        Err(anyhow!("{} can't be used as a file name", input))
+    } else {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::config::Profile;
@@ -21,8 +30,8 @@ mod tests {
     fn has_duplicates() {
         let input = "existing";
         let profile_catalog = indexmap! {
-            "existing".to_string() => Profile { name: "".to_string(), email: "".to_string(), signingkey: None },
-            "other".to_string() => Profile { name: "".to_string(), email: "".to_string(), signingkey: None }
+            "existing".to_string() => Profile { name: "".to_string(), email: "".to_string(), signingkey: None, ..Default::default() },
+            "other".to_string() => Profile { name: "".to_string(), email: "".to_string(), signingkey: None, ..Default::default() }
         };
         assert!(no_duplicates(input, &profile_catalog).is_err());
     }
@@ -32,9 +41,20 @@ mod tests {
     fn has_no_duplicates() {
         let input = "new";
         let profile_catalog = indexmap! {
-            "existing".to_string() => Profile { name: "".to_string(), email: "".to_string(), signingkey: None },
-            "other".to_string() => Profile { name: "".to_string(), email: "".to_string(), signingkey: None }
+            "existing".to_string() => Profile { name: "".to_string(), email: "".to_string(), signingkey: None, ..Default::default() },
+            "other".to_string() => Profile { name: "".to_string(), email: "".to_string(), signingkey: None, ..Default::default() }
         };
         assert!(no_duplicates(input, &profile_catalog).is_ok());
     }
+
+    #[test]
+    fn file_names() {
+        assert!(is_file_name("work").is_ok());
+        assert!(is_file_name("work.old").is_ok());
+        assert!(is_file_name("../x").is_err());
+        assert!(is_file_name("..").is_err());
+        assert!(is_file_name("a/b").is_err());
+        assert!(is_file_name("a\\b").is_err());
+        assert!(is_file_name("").is_err());
+    }
 }
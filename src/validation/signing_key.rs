@@ -0,0 +1,413 @@
+use anyhow::{anyhow, bail, Result};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{config::SigningFormat, paths::expand_home};
+
+/// How git interprets a `user.signingkey` value
+#[derive(Debug, Eq, PartialEq)]
+pub enum SigningKeyKind<'a> {
+    /// A GPG key ID or fingerprint
+    Gpg(&'a str),
+    /// A path to an SSH key file
+    SshFile(&'a str),
+    /// A literal SSH public key, e.g. `key::ssh-ed25519 AAAA...`
+    SshLiteral(&'a str),
+}
+
+pub fn kind_of(signingkey: &str) -> SigningKeyKind<'_> {
+    if let Some(literal) = signingkey.strip_prefix("key::") {
+        SigningKeyKind::SshLiteral(literal)
+    } else if signingkey.starts_with("ssh-") || signingkey.starts_with("ecdsa-") {
+        SigningKeyKind::SshLiteral(signingkey)
+    } else if signingkey.contains('/') || signingkey.starts_with('~') {
+        SigningKeyKind::SshFile(signingkey)
+    } else {
+        SigningKeyKind::Gpg(signingkey)
+    }
+}
+
+/// The gpg.format matching the given signing key
+pub fn format_of(signingkey: &str) -> SigningFormat {
+    match kind_of(signingkey) {
+        SigningKeyKind::Gpg(_) => SigningFormat::Openpgp,
+        SigningKeyKind::SshFile(_) | SigningKeyKind::SshLiteral(_) => SigningFormat::Ssh,
+    }
+}
+
+/// A secret key as listed by `gpg --list-secret-keys --with-colons`
+#[derive(Debug, Eq, PartialEq)]
+pub struct GpgKey {
+    pub fingerprint: String,
+    /// Whether GPG marked the key as expired or revoked
+    pub is_invalid: bool,
+    /// Expiration as a unix timestamp
+    pub expires_at: Option<u64>,
+    /// User IDs, e.g. `Git Smith <smith@example.com>`
+    pub uids: Vec<String>,
+}
+
+impl GpgKey {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.is_invalid || self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Whether one of the user IDs has the given email
+    pub fn has_email(&self, email: &str) -> bool {
+        self.uids.iter().any(|uid| {
+            uid_email(uid).is_some_and(|uid_email| uid_email.eq_ignore_ascii_case(email))
+        })
+    }
+}
+
+fn uid_email(uid: &str) -> Option<&str> {
+    let start = uid.rfind('<')?;
+    let end = uid[start..].find('>')? + start;
+    Some(&uid[start + 1..end])
+}
+
+/// Parses the machine readable output of `gpg --list-secret-keys --with-colons`.
+/// See https://github.com/gpg/gnupg/blob/master/doc/DETAILS
+pub fn parse_gpg_keys(output: &str) -> Vec<GpgKey> {
+    let mut keys: Vec<GpgKey> = vec![];
+    // Subkeys have fingerprints and user IDs of their own which must not be mixed up with the primary key
+    let mut in_primary_key = false;
+
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields[0] {
+            "sec" => {
+                in_primary_key = true;
+                keys.push(GpgKey {
+                    fingerprint: "".to_owned(),
+                    is_invalid: matches!(fields.get(1), Some(&"e") | Some(&"r")),
+                    expires_at: fields.get(6).and_then(|expires_at| expires_at.parse().ok()),
+                    uids: vec![],
+                });
+            }
+            "ssb" => in_primary_key = false,
+            "fpr" if in_primary_key => {
+                if let (Some(key), Some(fingerprint)) = (keys.last_mut(), fields.get(9)) {
+                    if key.fingerprint.is_empty() {
+                        key.fingerprint = fingerprint.to_string();
+                    }
+                }
+            }
+            "uid" if in_primary_key => {
+                if let (Some(key), Some(uid)) = (keys.last_mut(), fields.get(9)) {
+                    key.uids.push(uid.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    keys
+}
+
+/// Lists the secret keys in the keyring, optionally filtered by a key ID, fingerprint or email
+pub fn list_gpg_keys(maybe_filter: Option<&str>) -> Result<Vec<GpgKey>> {
+    list_gpg_keys_in(None, maybe_filter)
+}
+
+fn list_gpg_keys_in(maybe_gnupg_home: Option<&Path>, maybe_filter: Option<&str>) -> Result<Vec<GpgKey>> {
+    let mut cmd = Command::new("gpg");
+    if let Some(gnupg_home) = maybe_gnupg_home {
+        cmd.arg("--homedir").arg(gnupg_home);
+    }
+    cmd.args(["--list-secret-keys", "--with-colons", "--fixed-list-mode"]);
+    if let Some(filter) = maybe_filter {
+        cmd.arg(filter);
+    }
+    let output = cmd.output().map_err(|_| anyhow!("gpg is not available"))?;
+
+    // gpg fails when nothing matches the filter
+    Ok(parse_gpg_keys(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn verify_gpg(maybe_gnupg_home: Option<&Path>, key_id: &str, email: &str) -> Result<()> {
+    let keys = list_gpg_keys_in(maybe_gnupg_home, Some(key_id))?;
+    let key = match keys.first() {
+        Some(key) => key,
+        None => bail!("GPG secret key {} is not in the keyring", key_id),
+    };
+    if key.is_expired(now()) {
+        bail!("GPG key {} has expired", key_id);
+    }
+    if !key.has_email(email) {
+        bail!("GPG key {} has no user ID with {}", key_id, email);
+    }
+    Ok(())
+}
+
+/// Runs `ssh-keygen -l`, which only succeeds on valid keys
+fn verify_ssh(key_file: Option<&Path>, literal: Option<&str>) -> Result<()> {
+    let mut cmd = Command::new("ssh-keygen");
+    cmd.arg("-l").arg("-f");
+    match key_file {
+        Some(path) => cmd.arg(path),
+        None => cmd.arg("-"),
+    };
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| anyhow!("ssh-keygen is not available"))?;
+    if let (Some(mut stdin), Some(literal)) = (child.stdin.take(), literal) {
+        stdin.write_all(literal.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        bail!("Not a valid SSH key");
+    }
+    Ok(())
+}
+
+/// Checks that the given signing key can be found in the local GPG keyring or file system
+pub fn exists(signingkey: &str) -> Result<()> {
+    match kind_of(signingkey) {
+        SigningKeyKind::SshLiteral(_) => Ok(()),
+        SigningKeyKind::SshFile(path) => {
+            if expand_home(path).is_file() {
+                Ok(())
+            } else {
+                Err(anyhow!("SSH key file {} doesn't exist", path))
+            }
+        }
+        SigningKeyKind::Gpg(key_id) => {
+            if list_gpg_keys(Some(key_id))?.is_empty() {
+                bail!("GPG secret key {} is not in the keyring", key_id);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Checks that the given signing key exists, hasn't expired and, for GPG keys, belongs to the given email
+pub fn verify(signingkey: &str, email: &str) -> Result<()> {
+    match kind_of(signingkey) {
+        SigningKeyKind::Gpg(key_id) => verify_gpg(None, key_id, email),
+        SigningKeyKind::SshFile(path) => {
+            exists(signingkey)?;
+            verify_ssh(Some(&expand_home(path)), None)
+                .map_err(|_| anyhow!("{} is not a valid SSH key", path))
+        }
+        SigningKeyKind::SshLiteral(literal) => verify_ssh(None, Some(literal))
+            .map_err(|_| anyhow!("{} is not a valid SSH public key", signingkey)),
+    }
+}
+
+/// A signing key found on this machine
+#[derive(Debug, Eq, PartialEq)]
+pub struct Candidate {
+    /// The value for user.signingkey
+    pub signingkey: String,
+    pub format: SigningFormat,
+    pub description: String,
+}
+
+/// Lists the public keys (`*.pub`) in the given directory
+pub fn ssh_public_keys(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file() && path.extension().is_some_and(|extension| extension == "pub"))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Finds the GPG secret keys with a user ID matching the given email, and the SSH public keys in `~/.ssh`
+pub fn discover(email: &str) -> Vec<Candidate> {
+    let now = now();
+    let gpg_candidates = list_gpg_keys(Some(email))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|key| key.has_email(email) && !key.is_expired(now))
+        .map(|key| Candidate {
+            description: format!("GPG {} {}", key.fingerprint, key.uids.join(", ")),
+            signingkey: key.fingerprint,
+            format: SigningFormat::Openpgp,
+        });
+
+    let ssh_candidates = ssh_public_keys(&expand_home("~/.ssh")).into_iter().map(|path| {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let signingkey = format!("~/.ssh/{}", file_name);
+        // The comment of the key is usually where it comes from, e.g. an email
+        let comment = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| content.split_whitespace().nth(2).map(str::to_owned))
+            .unwrap_or_default();
+        Candidate {
+            description: format!("SSH {} {}", signingkey, comment).trim_end().to_owned(),
+            signingkey,
+            format: SigningFormat::Ssh,
+        }
+    });
+
+    gpg_candidates.chain(ssh_candidates).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds() {
+        assert_eq!(kind_of("ABCDEF0123456789"), SigningKeyKind::Gpg("ABCDEF0123456789"));
+        assert_eq!(kind_of("~/.ssh/id_ed25519.pub"), SigningKeyKind::SshFile("~/.ssh/id_ed25519.pub"));
+        assert_eq!(kind_of("/keys/id_rsa"), SigningKeyKind::SshFile("/keys/id_rsa"));
+        assert_eq!(kind_of("key::ssh-ed25519 AAAA"), SigningKeyKind::SshLiteral("ssh-ed25519 AAAA"));
+        assert_eq!(kind_of("ssh-rsa AAAA"), SigningKeyKind::SshLiteral("ssh-rsa AAAA"));
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(format_of("ABCDEF0123456789"), SigningFormat::Openpgp);
+        assert_eq!(format_of("~/.ssh/id_ed25519.pub"), SigningFormat::Ssh);
+        assert_eq!(format_of("key::ssh-ed25519 AAAA"), SigningFormat::Ssh);
+    }
+
+    #[test]
+    fn lists_ssh_public_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        ["id_rsa", "id_rsa.pub", "id_ed25519.pub", "config", "known_hosts"]
+            .iter()
+            .for_each(|name| fs::write(dir.path().join(name), "").unwrap());
+        assert_eq!(
+            ssh_public_keys(dir.path()),
+            vec![dir.path().join("id_ed25519.pub"), dir.path().join("id_rsa.pub")]
+        );
+        assert!(ssh_public_keys(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn missing_ssh_key_file() {
+        assert!(exists("/nonexistent/id_ed25519.pub").is_err());
+        assert!(exists("key::ssh-ed25519 AAAA").is_ok());
+        assert!(verify("/nonexistent/id_ed25519.pub", "a@b.com").is_err());
+    }
+
+    #[test]
+    fn parses_gpg_keys() {
+        let keys = parse_gpg_keys(
+            "sec:u:255:22:A4B3293FFF57A04A:1792363061:1792449461::u:::scSC:::+::ed25519:::0:
+fpr:::::::::C533CA66BFD606C08A811BC0A4B3293FFF57A04A:
+uid:u::::1792363061::F2E7::Test User <test@example.com>::::::::::0:
+ssb:u:255:18:1234567890ABCDEF:1792363061::::::e:::+::cv25519::
+fpr:::::::::0000000000000000000000001234567890ABCDEF:
+sec:e:255:22:05B0BB21BE226105:1577836800:1577923200::u:::sc:::+::ed25519:::0:
+fpr:::::::::7B03EDFD4F4802A58EE711A005B0BB21BE226105:
+uid:e::::1577836800::5548::Old <old@example.com>::::::::::0:
+",
+        );
+        assert_eq!(
+            keys,
+            vec![
+                GpgKey {
+                    fingerprint: "C533CA66BFD606C08A811BC0A4B3293FFF57A04A".to_string(),
+                    is_invalid: false,
+                    expires_at: Some(1792449461),
+                    uids: vec!["Test User <test@example.com>".to_string()],
+                },
+                GpgKey {
+                    fingerprint: "7B03EDFD4F4802A58EE711A005B0BB21BE226105".to_string(),
+                    is_invalid: true,
+                    expires_at: Some(1577923200),
+                    uids: vec!["Old <old@example.com>".to_string()],
+                },
+            ]
+        );
+        assert!(keys[0].has_email("TEST@example.com"));
+        assert!(!keys[0].has_email("other@example.com"));
+        assert!(!keys[0].is_expired(1792363061));
+        assert!(keys[0].is_expired(1792449461));
+        assert!(keys[1].is_expired(0));
+    }
+
+    fn gpg(gnupg_home: &Path, args: &[&str]) -> bool {
+        Command::new("gpg")
+            .arg("--homedir")
+            .arg(gnupg_home)
+            .args(["--batch", "--passphrase", ""])
+            .args(args)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    #[test]
+    fn verifies_against_gpg_keyring() {
+        let gnupg_home = tempfile::tempdir().unwrap();
+        if !gpg(gnupg_home.path(), &["--version"]) {
+            eprintln!("Skipping as gpg is not available");
+            return;
+        }
+        assert!(gpg(
+            gnupg_home.path(),
+            &["--quick-gen-key", "Test User <test@example.com>", "ed25519", "sign", "1y"]
+        ));
+        assert!(gpg(
+            gnupg_home.path(),
+            &["--faked-system-time", "20200101T000000", "--quick-gen-key", "Old <old@example.com>", "ed25519", "sign", "1d"]
+        ));
+
+        let home = Some(gnupg_home.path());
+        assert!(verify_gpg(home, "test@example.com", "test@example.com").is_ok());
+        assert_eq!(
+            verify_gpg(home, "test@example.com", "other@example.com").unwrap_err().to_string(),
+            "GPG key test@example.com has no user ID with other@example.com"
+        );
+        assert_eq!(
+            verify_gpg(home, "old@example.com", "old@example.com").unwrap_err().to_string(),
+            "GPG key old@example.com has expired"
+        );
+        assert_eq!(
+            verify_gpg(home, "DEADBEEF", "test@example.com").unwrap_err().to_string(),
+            "GPG secret key DEADBEEF is not in the keyring"
+        );
+
+        Command::new("gpgconf")
+            .arg("--homedir")
+            .arg(gnupg_home.path())
+            .args(["--kill", "gpg-agent"])
+            .output()
+            .ok();
+    }
+
+    #[test]
+    fn verifies_ssh_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("id_ed25519");
+        let generated = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "test@example.com", "-f"])
+            .arg(&key_path)
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if !generated {
+            eprintln!("Skipping as ssh-keygen is not available");
+            return;
+        }
+
+        let public_key_path = dir.path().join("id_ed25519.pub");
+        assert!(verify(public_key_path.to_str().unwrap(), "test@example.com").is_ok());
+
+        let literal = std::fs::read_to_string(&public_key_path).unwrap();
+        assert!(verify(&format!("key::{}", literal.trim()), "test@example.com").is_ok());
+        assert!(verify("key::ssh-ed25519 garbage", "test@example.com").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+// Each test binary only uses part of the sandbox
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use assert_cmd::prelude::*;
+
+/// A repository with its own home, so that neither the user's git config nor profiles get involved
+pub struct Sandbox {
+    _dir: tempfile::TempDir,
+    home: PathBuf,
+    pub repo: PathBuf,
+    config_path: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("home");
+        let repo = dir.path().join("repo");
+        let config_path = dir.path().join("gitprofile.toml");
+        fs::create_dir_all(&home).unwrap();
+        fs::write(
+            &config_path,
+            "[profile.work]\nname = \"Foo Bar\"\nemail = \"foo@work.com\"\n",
+        )
+        .unwrap();
+
+        let sandbox = Sandbox { _dir: dir, home, repo, config_path };
+        sandbox.git(Path::new("."), &["init", "-q", sandbox.repo.to_str().unwrap()]);
+        sandbox
+    }
+
+    /// Puts git-profile on the PATH, as the installed hooks call `git profile`
+    pub fn command(&self, program: &str) -> Command {
+        let bin_dir = Path::new(env!("CARGO_BIN_EXE_git-profile")).parent().unwrap();
+        let path = env::join_paths(
+            std::iter::once(bin_dir.to_path_buf()).chain(env::split_paths(&env::var_os("PATH").unwrap())),
+        )
+        .unwrap();
+
+        let mut command = Command::new(program);
+        command
+            .current_dir(&self.repo)
+            .env("HOME", &self.home)
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env_remove("GIT_DIR")
+            .env("PATH", path);
+        command
+    }
+
+    pub fn git(&self, current_dir: &Path, args: &[&str]) {
+        let mut command = self.command("git");
+        command.current_dir(current_dir).args(args).assert().success();
+    }
+
+    /// Runs git in the repository, returning its trimmed stdout
+    pub fn git_stdout(&self, args: &[&str]) -> String {
+        let output = self.command("git").args(args).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8(output.stdout).unwrap().trim().to_owned()
+    }
+
+    pub fn git_profile(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        self.git_profile_in(&self.repo, args)
+    }
+
+    pub fn git_profile_in(&self, current_dir: &Path, args: &[&str]) -> assert_cmd::assert::Assert {
+        let mut command = self.command(env!("CARGO_BIN_EXE_git-profile"));
+        command.current_dir(current_dir).arg("-c").arg(&self.config_path).args(args).assert()
+    }
+
+    pub fn write_hook(&self, hook_name: &str, body: &str) {
+        self.write_hook_in(&self.repo.join(".git/hooks"), hook_name, body);
+    }
+
+    /// Writes a hook in a hooks directory of its own, as set by core.hooksPath
+    pub fn write_hook_in(&self, hooks_dir: &Path, hook_name: &str, body: &str) {
+        fs::create_dir_all(hooks_dir).unwrap();
+        let hook_path = hooks_dir.join(hook_name);
+        fs::write(&hook_path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    /// A hooks directory set as the global core.hooksPath, like the ones of husky or of a company
+    pub fn global_hooks_dir(&self) -> PathBuf {
+        let hooks_dir = self.home.join("hooks");
+        self.git(&self.repo, &["config", "--global", "core.hooksPath", hooks_dir.to_str().unwrap()]);
+        hooks_dir
+    }
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::Sandbox;
+
+/// A branch with two commits made with the wrong identity on top of a `base` tag
+fn sandbox_with_commits() -> Sandbox {
+    let sandbox = Sandbox::new();
+    sandbox.git(&sandbox.repo, &["config", "user.name", "Someone"]);
+    sandbox.git(&sandbox.repo, &["config", "user.email", "someone@example.com"]);
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "Base"]);
+    sandbox.git(&sandbox.repo, &["tag", "base"]);
+    sandbox.git(&sandbox.repo, &["checkout", "-q", "-b", "feature"]);
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "First"]);
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "Second"]);
+    sandbox
+}
+
+#[test]
+fn rewrites_the_commits_and_backs_up_the_branch() {
+    let sandbox = sandbox_with_commits();
+    let original_head = sandbox.git_stdout(&["rev-parse", "HEAD"]);
+
+    sandbox.git_profile(&["fix-commits", "--profile", "work", "--onto", "base"]).success();
+
+    assert_eq!(
+        sandbox.git_stdout(&["log", "--format=%s %an <%ae> %cn <%ce>", "base..feature"]),
+        "Second Foo Bar <foo@work.com> Foo Bar <foo@work.com>\nFirst Foo Bar <foo@work.com> Foo Bar <foo@work.com>"
+    );
+    assert_eq!(
+        sandbox.git_stdout(&["log", "-1", "--format=%an <%ae>", "base"]),
+        "Someone <someone@example.com>"
+    );
+    assert_eq!(sandbox.git_stdout(&["rev-parse", "refs/gitprofile/backup/feature"]), original_head);
+}
+
+#[test]
+fn keeps_an_existing_backup_unless_forced() {
+    let sandbox = sandbox_with_commits();
+    let original_head = sandbox.git_stdout(&["rev-parse", "HEAD"]);
+    sandbox.git_profile(&["fix-commits", "--profile", "work", "--onto", "base"]).success();
+    let first_rewrite = sandbox.git_stdout(&["rev-parse", "HEAD"]);
+
+    sandbox.git_profile(&["fix-commits", "--profile", "work", "--onto", "base"]).failure();
+    assert_eq!(sandbox.git_stdout(&["rev-parse", "refs/gitprofile/backup/feature"]), original_head);
+    assert_eq!(sandbox.git_stdout(&["rev-parse", "HEAD"]), first_rewrite);
+
+    sandbox.git_profile(&["fix-commits", "--profile", "work", "--onto", "base", "--force"]).success();
+    assert_eq!(sandbox.git_stdout(&["rev-parse", "refs/gitprofile/backup/feature"]), first_rewrite);
+}
+
+#[test]
+fn refuses_a_detached_head() {
+    let sandbox = sandbox_with_commits();
+    sandbox.git(&sandbox.repo, &["checkout", "-q", "--detach"]);
+
+    sandbox.git_profile(&["fix-commits", "--profile", "work", "--onto", "base"]).failure();
+    let backup = sandbox.command("git").args(["rev-parse", "--verify", "--quiet", "refs/gitprofile/backup/HEAD"]).status();
+    assert!(!backup.unwrap().success());
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use std::fs;
+
+use common::Sandbox;
+
+#[test]
+fn applies_the_profile_in_each_repository() {
+    let sandbox = Sandbox::new();
+    let root = sandbox.repo.parent().unwrap().to_path_buf();
+    let restricted = root.join("restricted");
+    sandbox.git(&root, &["init", "-q", restricted.to_str().unwrap()]);
+    fs::write(restricted.join(".gitprofile-policy.toml"), "email = '@acme\\.com$'\n").unwrap();
+
+    // Run from the home, outside of the repositories
+    sandbox
+        .git_profile_in(&root.join("home"), &["foreach", "--root", root.to_str().unwrap(), "--profile", "work"])
+        .failure();
+
+    let email_of = |repository: &std::path::Path| {
+        let output = sandbox
+            .command("git")
+            .current_dir(repository)
+            .args(["config", "--local", "user.email"])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_owned()
+    };
+    assert_eq!(email_of(&sandbox.repo), "foo@work.com");
+    // The policy of the repository itself applies
+    assert_eq!(email_of(&restricted), "");
+}
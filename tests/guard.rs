@@ -0,0 +1,54 @@
+mod common;
+
+use assert_cmd::prelude::*;
+
+use common::Sandbox;
+
+#[test]
+fn hooks_of_the_repository_still_run() {
+    let sandbox = Sandbox::new();
+    sandbox.write_hook("commit-msg", "echo 'Reviewed-by: commit-msg' >> \"$1\"");
+    sandbox.git_profile(&["apply", "work", "--local"]).success();
+    sandbox.git_profile(&["guard", "install"]).success();
+
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "First"]);
+
+    assert_eq!(sandbox.git_stdout(&["log", "-1", "--format=%B"]), "First\nReviewed-by: commit-msg");
+}
+
+#[test]
+fn identity_is_checked_after_the_chained_hook() {
+    let sandbox = Sandbox::new();
+    sandbox.write_hook("pre-commit", "touch pre-commit-ran");
+    sandbox.git_profile(&["guard", "install"]).success();
+    sandbox.git(&sandbox.repo, &["config", "user.name", "Someone"]);
+    sandbox.git(&sandbox.repo, &["config", "user.email", "someone@example.com"]);
+
+    sandbox
+        .command("git")
+        .args(["commit", "-q", "--allow-empty", "-m", "First"])
+        .assert()
+        .failure();
+    assert!(sandbox.repo.join("pre-commit-ran").exists());
+}
+
+#[test]
+fn global_hooks_still_run_under_a_local_guard() {
+    let sandbox = Sandbox::new();
+    let global_hooks_dir = sandbox.global_hooks_dir();
+    sandbox.write_hook_in(&global_hooks_dir, "commit-msg", "echo 'Reviewed-by: global' >> \"$1\"");
+    sandbox.git_profile(&["apply", "work", "--local"]).success();
+    sandbox.git_profile(&["guard", "install"]).success();
+
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "First"]);
+    assert_eq!(sandbox.git_stdout(&["log", "-1", "--format=%B"]), "First\nReviewed-by: global");
+
+    // The global hooks path applies again, without being copied to the repository
+    sandbox.git_profile(&["guard", "uninstall"]).success();
+    assert_eq!(sandbox.git_stdout(&["config", "core.hooksPath"]), global_hooks_dir.to_str().unwrap());
+    sandbox
+        .command("git")
+        .args(["config", "--local", "core.hooksPath"])
+        .assert()
+        .failure();
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use assert_cmd::prelude::*;
+
+use common::Sandbox;
+
+/// A repository pairing with alice, committing with an identity that matches no profile
+fn pairing_sandbox() -> Sandbox {
+    let sandbox = Sandbox::new();
+    start_pairing(&sandbox);
+    sandbox
+}
+
+fn start_pairing(sandbox: &Sandbox) {
+    sandbox.git(&sandbox.repo, &["config", "user.name", "Someone"]);
+    sandbox.git(&sandbox.repo, &["config", "user.email", "someone@example.com"]);
+    sandbox
+        .git_profile(&["teammate", "add", "alice", "--name", "Alice Doe", "--email", "alice@work.com"])
+        .success();
+    sandbox.git_profile(&["pair", "alice"]).success();
+}
+
+#[test]
+fn adds_trailers_to_commits_made_without_the_editor() {
+    let sandbox = pairing_sandbox();
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "First"]);
+    assert_eq!(
+        sandbox.git_stdout(&["log", "-1", "--format=%B"]),
+        "First\n\nCo-authored-by: Alice Doe <alice@work.com>"
+    );
+
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--amend", "--allow-empty", "--no-edit"]);
+    assert_eq!(
+        sandbox.git_stdout(&["log", "-1", "--format=%B"]),
+        "First\n\nCo-authored-by: Alice Doe <alice@work.com>"
+    );
+}
+
+#[test]
+fn stops_adding_trailers_once_cleared() {
+    let sandbox = pairing_sandbox();
+    sandbox.git_profile(&["pair", "--clear"]).success();
+
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "First"]);
+    assert_eq!(sandbox.git_stdout(&["log", "-1", "--format=%B"]), "First");
+    assert!(!sandbox.repo.join(".git/gitprofile-hooks").exists());
+}
+
+#[test]
+fn keeps_the_hooks_of_the_guard() {
+    let sandbox = pairing_sandbox();
+    sandbox.git_profile(&["guard", "install"]).success();
+    sandbox.git_profile(&["pair", "--clear"]).success();
+
+    // Someone <someone@example.com> matches no profile
+    sandbox
+        .command("git")
+        .args(["commit", "-q", "--allow-empty", "-m", "First"])
+        .assert()
+        .failure();
+
+    sandbox.git_profile(&["guard", "uninstall"]).success();
+    assert!(!sandbox.repo.join(".git/gitprofile-hooks").exists());
+}
+
+#[test]
+fn lists_the_roster() {
+    let sandbox = pairing_sandbox();
+    sandbox.git_profile(&["teammate", "add", "bob", "--name", "Bob Roe", "--email", "bob@work.com"]).success();
+    sandbox.git_profile(&["teammate", "add", "bob", "--name", "Bob", "--email", "bob@oss.org"]).failure();
+
+    sandbox
+        .git_profile(&["teammate", "list"])
+        .success()
+        .stdout("alice  Alice Doe <alice@work.com>\nbob    Bob Roe <bob@work.com>\n");
+}
+
+#[test]
+fn global_hooks_still_run_while_pairing() {
+    let sandbox = Sandbox::new();
+    let global_hooks_dir = sandbox.global_hooks_dir();
+    sandbox.write_hook_in(&global_hooks_dir, "commit-msg", "echo 'Reviewed-by: global' >> \"$1\"");
+    start_pairing(&sandbox);
+
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "First"]);
+    assert_eq!(
+        sandbox.git_stdout(&["log", "-1", "--format=%B"]),
+        "First\n\nCo-authored-by: Alice Doe <alice@work.com>\nReviewed-by: global"
+    );
+}
+
+#[test]
+fn adds_trailers_under_the_global_guard() {
+    let sandbox = Sandbox::new();
+    sandbox.git_profile(&["guard", "install", "--global"]).success();
+    sandbox.write_hook("commit-msg", "echo 'Reviewed-by: commit-msg' >> \"$1\"");
+    start_pairing(&sandbox);
+    sandbox.git_profile(&["apply", "work", "--local"]).success();
+
+    sandbox.git(&sandbox.repo, &["commit", "-q", "--allow-empty", "-m", "First"]);
+    assert_eq!(
+        sandbox.git_stdout(&["log", "-1", "--format=%B"]),
+        "First\n\nCo-authored-by: Alice Doe <alice@work.com>\nReviewed-by: commit-msg"
+    );
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use std::fs;
+
+use common::Sandbox;
+
+#[test]
+fn reports_broken_repositories_and_goes_on() {
+    let sandbox = Sandbox::new();
+    let root = sandbox.repo.parent().unwrap().to_path_buf();
+    sandbox.git_profile(&["apply", "work", "--local"]).success();
+    let broken = root.join("broken");
+    sandbox.git(&root, &["init", "-q", broken.to_str().unwrap()]);
+    fs::write(broken.join(".git/config"), "[core\n").unwrap();
+
+    // Run from the home, outside of the repositories
+    let output = sandbox
+        .git_profile_in(&root.join("home"), &["scan", root.to_str().unwrap(), "--format", "json"])
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let reports: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let reports = reports.as_array().unwrap();
+
+    let report_of = |path: &std::path::Path| {
+        reports.iter().find(|report| report["path"] == path.to_str().unwrap()).unwrap()
+    };
+    assert!(report_of(&broken)["error"].is_string());
+    assert_eq!(report_of(&sandbox.repo)["levels"]["local"]["profile"], "work");
+    assert!(report_of(&sandbox.repo).get("error").is_none());
+}